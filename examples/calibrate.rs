@@ -0,0 +1,29 @@
+use conifer::prelude::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut config = Config::auto()?;
+    // taps have to be measured without any previous calibration applied
+    config.set_calibration(Calibration::identity());
+    let mut calibrator = Calibrator::new(config.screen_width(), config.screen_height());
+    let black = color_from_rgb(0, 0, 0);
+    let white = color_from_rgb(255, 255, 255);
+    config.run(move |canvas, event| {
+        match event {
+            Event::Startup => {}
+            Event::Swipe(swipe) => {
+                if let Some(calibration) = calibrator.update(&swipe) {
+                    calibration.save(Calibration::default_path())?;
+                    return Ok(RunResponse::Exit);
+                }
+            }
+            _ => return Ok(RunResponse::NothingChanged),
+        }
+        // tap the center of each crosshair as it appears
+        for p in canvas.pixels.iter_mut() {
+            *p = black;
+        }
+        calibrator.draw(canvas, white);
+        Ok(RunResponse::Draw)
+    })?;
+    Ok(())
+}
@@ -14,6 +14,8 @@ fn main() -> Result<(), Box<dyn Error>> {
             &img_pine,
             (random() * canvas.width as f32) as isize - img_pine.width as isize / 2,
             (random() * canvas.height as f32) as isize - img_pine.height as isize / 2,
+            BlendMode::Replace,
+            1.0,
         )?;
         Ok(RunResponse::Draw)
     })?;
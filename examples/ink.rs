@@ -0,0 +1,22 @@
+use conifer::prelude::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let ink = Ink::new(2.0, 10.0);
+    let white = color_from_rgb(255, 255, 255);
+    Config::auto()?.run(move |canvas, event| {
+        if let Event::Swipe(swipe) = event {
+            if swipe
+                .points
+                .iter()
+                .any(|p| p.x > canvas.width as isize - 50)
+            {
+                // exit if we touch the right edge of the screen
+                return Ok(RunResponse::Exit);
+            }
+            // the whole stroke so far is redrawn as the curve settles
+            canvas.draw_ink(&ink.path(&swipe), white);
+        }
+        Ok(RunResponse::Draw)
+    })?;
+    Ok(())
+}
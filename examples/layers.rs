@@ -0,0 +1,42 @@
+use conifer::prelude::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let img_pine = load_image("examples/images/pine.png")?;
+    let mut c = Config::auto()?;
+    let (width, height) = (c.screen_width(), c.screen_height());
+    let mut layers = LayerStack::new(width, height);
+    layers.add_canvas(
+        "background",
+        0,
+        Canvas::from_color(width, height, 0, 100, 0),
+    );
+    layers.add_canvas("sprite", 1, img_pine);
+    layers.add("hud", 2);
+    let font = Font::default();
+    let white = color_from_rgb(255, 255, 255);
+    let mut ticks = 0;
+    c.run(move |canvas, event| {
+        match event {
+            Event::Swipe(s) if s.finished => return Ok(RunResponse::Exit),
+            Event::Timer(_, _) => {
+                // the sprite moves every tick, the hud only changes once a second
+                ticks += 1;
+                let sprite = layers.get_mut("sprite").unwrap();
+                sprite.x = (ticks * 4) % width as isize;
+                if ticks % 60 == 0 {
+                    let hud = layers.canvas_mut("hud").unwrap();
+                    hud.pixels.iter_mut().for_each(|p| *p = 0);
+                    hud.draw_text(10, 10, &format!("ticks: {}", ticks), white, &font);
+                }
+            }
+            _ => {}
+        }
+        // only the areas the sprite and hud moved over need pushing out
+        if layers.composite(canvas)? {
+            Ok(RunResponse::DrawDamaged)
+        } else {
+            Ok(RunResponse::NothingChanged)
+        }
+    })?;
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use conifer::prelude::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = std::env::args().nth(1);
+    let image = load_image(path.as_deref().unwrap_or("examples/images/pine.png"))?;
+    let mut c = Config::auto()?;
+    let (width, height) = (c.screen_width(), c.screen_height());
+    // averaging suits shrinking big photos, blending suits enlarging small ones
+    let filter = if image.width > width || image.height > height {
+        Filter::Box
+    } else {
+        Filter::Bilinear
+    };
+    // letterboxed and cropped versions of the photo, tapping swaps between them
+    let photos = [
+        image.fit(width, height, filter, color_from_rgb(0, 0, 0)),
+        image.fill(width, height, filter),
+    ];
+    let mut showing = 0;
+    c.run(move |canvas, event| {
+        match event {
+            Event::Startup => {}
+            Event::Swipe(s) if s.finished => {
+                if s.points.iter().any(|p| p.y < 50) {
+                    return Ok(RunResponse::Exit);
+                }
+                showing = 1 - showing;
+            }
+            _ => return Ok(RunResponse::NothingChanged),
+        }
+        canvas.copy_from_canvas(&photos[showing])?;
+        Ok(RunResponse::Draw)
+    })?;
+    Ok(())
+}
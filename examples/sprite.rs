@@ -0,0 +1,39 @@
+use conifer::prelude::*;
+use std::sync::Arc;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // a sheet of four 32x32 frames of a growing arrow, drawn here rather than loaded
+    let mut image = Canvas::new(128, 32, &[0; 128 * 32]);
+    for i in 0..4 {
+        let left = i * 32;
+        let tip = left + 16 + i * 5;
+        image.fill_triangle((left, 0), (tip, 16), (left, 31), 0xffff_8000);
+    }
+    let sheet = Arc::new(SpriteSheet::from_grid(&image, 32, 32)?);
+    let mut sprite = AnimatedSprite::new(sheet);
+    sprite.add_clip(
+        "pulse",
+        Clip::new(vec![0, 1, 2, 3], 120, LoopMode::PingPong),
+    )?;
+    sprite.play("pulse")?;
+
+    let mut c = Config::auto()?;
+    let background = Canvas::from_color(c.screen_width(), c.screen_height(), 0, 0, 0);
+    c.run(move |canvas, event| {
+        match event {
+            Event::Timer(delta, _) => sprite.update(delta),
+            // tapping flips the sprite, tapping the top of the screen exits
+            Event::Swipe(s) if s.finished => {
+                if s.points.iter().any(|p| p.y < 50) {
+                    return Ok(RunResponse::Exit);
+                }
+                sprite.flip_x = !sprite.flip_x;
+            }
+            _ => {}
+        }
+        canvas.copy_from_canvas(&background)?;
+        sprite.draw(canvas, 100, 100)?;
+        Ok(RunResponse::Draw)
+    })?;
+    Ok(())
+}
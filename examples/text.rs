@@ -0,0 +1,39 @@
+use conifer::prelude::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // pass a console font such as /usr/share/consolefonts/Lat2-Terminus16.psf.gz,
+    // or a TrueType font and a pixel size, to use it
+    let mut args = std::env::args().skip(1);
+    let font = match (args.next(), args.next()) {
+        (Some(path), Some(size)) => Font::load_truetype(path, size.parse()?)?,
+        (Some(path), None) => Font::load(path)?,
+        _ => Font::default(),
+    };
+    let black = color_from_rgb(0, 0, 0);
+    let white = color_from_rgb(255, 255, 255);
+    let mut taps = 0;
+    Config::auto()?.run(move |canvas, event| {
+        if let Event::Swipe(s) = event {
+            if s.finished {
+                taps += 1;
+            }
+        }
+        if taps >= 10 {
+            return Ok(RunResponse::Exit);
+        }
+        for p in canvas.pixels.iter_mut() {
+            *p = black;
+        }
+        let text = format!("Hello, conifer!\ntaps: {}/10", taps);
+        let (w, h) = font.measure(&text);
+        canvas.draw_text(
+            (canvas.width as isize - w as isize) / 2,
+            (canvas.height as isize - h as isize) / 2,
+            &text,
+            white,
+            &font,
+        );
+        Ok(RunResponse::Draw)
+    })?;
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use conifer::framebuffer::Framebuffer;
+use conifer::input::recorded_input::ReplayInput;
+use conifer::prelude::*;
+
+// cargo run --example trace record touches.trace
+// cargo run --example trace replay touches.trace
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    let path = args.get(2).ok_or("usage: trace record|replay <file>")?;
+    let mut c = match args.get(1).map(String::as_str) {
+        Some("record") => {
+            let mut c = Config::auto()?;
+            c.record_input(path)?;
+            c
+        }
+        Some("replay") => {
+            let input = ReplayInput::load(path, true)?;
+            let mut c = Config::with_backends(Framebuffer::auto()?, input);
+            // traces hold touches from before calibration, like the device gives
+            c.set_calibration(Calibration::load_default()?);
+            c
+        }
+        _ => return Err("usage: trace record|replay <file>".into()),
+    };
+
+    let white = color_from_rgb(255, 255, 255);
+    c.run(move |canvas, event| {
+        if let Event::Swipe(swipe) = event {
+            if swipe.points.iter().any(|p| p.y < 50) {
+                // exit if we touch the top of the screen
+                return Ok(RunResponse::Exit);
+            }
+            let ink = Ink::new(1.0, 6.0).path(&swipe);
+            canvas.draw_ink(&ink, white);
+            return Ok(RunResponse::DrawDamaged);
+        }
+        Ok(RunResponse::NothingChanged)
+    })?;
+    Ok(())
+}
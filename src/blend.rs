@@ -0,0 +1,155 @@
+//! Compositing of ARGB colors, alpha in bits 24-31 as `load_image` and
+//! `color_from_rgb` pack it.
+
+/// Composite `src` over `dst` with `alpha` as the source's opacity, 0 for
+/// fully transparent through 255 for opaque. Colors aren't premultiplied.
+pub fn source_over(dst: u32, src: u32, alpha: u8) -> u32 {
+    match alpha {
+        0 => return dst,
+        255 => return src | 0xff << 24,
+        _ => {}
+    }
+    let sa = alpha as u32;
+    let da = dst >> 24;
+    // destination alpha scaled by what the source lets through, times 255
+    let dw = da * (255 - sa);
+    let out_a = sa * 255 + dw;
+    if out_a == 0 {
+        return 0;
+    }
+    let mix = |shift: u32| {
+        let s = (src >> shift) & 0xff;
+        let d = (dst >> shift) & 0xff;
+        ((s * sa * 255 + d * dw + out_a / 2) / out_a) << shift
+    };
+    ((out_a + 127) / 255) << 24 | mix(16) | mix(8) | mix(0)
+}
+
+/// How a source pixel combines with the destination pixel under it when one
+/// canvas is drawn onto another.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Replace the destination, alpha included, ignoring what's underneath.
+    Replace,
+    /// Ordinary source-over alpha compositing.
+    #[default]
+    Normal,
+    /// Add the colors together, saturating at white, as for glows.
+    Add,
+    /// Multiply the colors, only ever darkening, as for shadows.
+    Multiply,
+    /// Multiply the inverted colors, only ever lightening.
+    Screen,
+    /// Exclusive or of the color bits, drawn twice it restores what was there,
+    /// as for cursors.
+    Xor,
+}
+
+impl BlendMode {
+    /// Combine `src` with `dst`, where `alpha` is how opaque the source is,
+    /// 0 for no change through 255 for full strength.
+    pub fn blend(self, dst: u32, src: u32, alpha: u8) -> u32 {
+        let channel = |f: fn(u32, u32) -> u32| {
+            let mix = |shift: u32| f((src >> shift) & 0xff, (dst >> shift) & 0xff) << shift;
+            (src & 0xff00_0000) | mix(16) | mix(8) | mix(0)
+        };
+        let mixed = match self {
+            BlendMode::Replace => return lerp(dst, src, alpha),
+            BlendMode::Normal => src,
+            BlendMode::Add => channel(|s, d| (s + d).min(255)),
+            BlendMode::Multiply => channel(|s, d| (s * d + 127) / 255),
+            BlendMode::Screen => channel(|s, d| 255 - ((255 - s) * (255 - d) + 127) / 255),
+            BlendMode::Xor => channel(|s, d| s ^ d),
+        };
+        source_over(dst, mixed, alpha)
+    }
+}
+
+// move each channel of dst, alpha included, towards src
+fn lerp(dst: u32, src: u32, alpha: u8) -> u32 {
+    let a = alpha as u32;
+    let mix = |shift: u32| {
+        let s = (src >> shift) & 0xff;
+        let d = (dst >> shift) & 0xff;
+        ((s * a + d * (255 - a) + 127) / 255) << shift
+    };
+    mix(24) | mix(16) | mix(8) | mix(0)
+}
+
+/// Scale `alpha` by an opacity between 0.0 and 1.0.
+pub fn fade(alpha: u8, opacity: f32) -> u8 {
+    (alpha as f32 * opacity.clamp(0.0, 1.0)).round() as u8
+}
+
+/// The alpha of a packed color.
+pub fn alpha(color: u32) -> u8 {
+    (color >> 24) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extremes_copy_or_keep() {
+        assert_eq!(source_over(0xff10_2030, 0x0040_5060, 255), 0xff40_5060);
+        assert_eq!(source_over(0xff10_2030, 0xff40_5060, 0), 0xff10_2030);
+    }
+
+    #[test]
+    fn half_transparent_over_opaque() {
+        // (200 * 128 + 100 * 127) / 255 = 150.2 and so on
+        assert_eq!(source_over(0xff64_0000, 0x80c8_ff00, 128), 0xff96_8000);
+        // a quarter of white over black
+        assert_eq!(source_over(0xff00_0000, 0x40ff_ffff, 64), 0xff40_4040);
+    }
+
+    #[test]
+    fn over_transparent_keeps_source_color() {
+        // nothing underneath, so the color is the source's and only alpha drops
+        assert_eq!(source_over(0x0000_0000, 0x80c8_6432, 128), 0x80c8_6432);
+        assert_eq!(source_over(0x0000_0000, 0x0000_0000, 0), 0);
+    }
+
+    #[test]
+    fn half_transparent_over_half_transparent() {
+        // out alpha = 0.5 + 0.5 * 0.5 = 0.75, red = (1 * 0.5) / 0.75
+        let out = source_over(0x8000_00ff, 0x80ff_0000, 128);
+        assert_eq!(alpha(out), 0xc0);
+        assert_eq!((out >> 16) & 0xff, 0xaa);
+        assert_eq!(out & 0xff, 0x55);
+    }
+
+    #[test]
+    fn blend_modes_at_full_strength() {
+        let dst = 0xff80_4020;
+        let src = 0xffa0_c0ff;
+        assert_eq!(BlendMode::Replace.blend(dst, 0x10a0_c0ff, 255), 0x10a0_c0ff);
+        assert_eq!(BlendMode::Normal.blend(dst, src, 255), src);
+        assert_eq!(BlendMode::Add.blend(dst, src, 255), 0xffff_ffff);
+        // 0x80 * 0xa0 / 255 = 80.3, 0x40 * 0xc0 / 255 = 48.2, 0x20 * 0xff / 255 = 32
+        assert_eq!(BlendMode::Multiply.blend(dst, src, 255), 0xff50_3020);
+        // 255 - 0x7f * 0x5f / 255 = 207.7, 255 - 0xbf * 0x3f / 255 = 207.8
+        assert_eq!(BlendMode::Screen.blend(dst, src, 255), 0xffd0_d0ff);
+        assert_eq!(BlendMode::Xor.blend(dst, src, 255), 0xff20_80df);
+    }
+
+    #[test]
+    fn blend_modes_respect_alpha() {
+        let dst = 0xff40_4040;
+        let src = 0xff40_4040;
+        for mode in [BlendMode::Normal, BlendMode::Add, BlendMode::Xor].iter() {
+            assert_eq!(mode.blend(dst, src, 0), dst);
+        }
+        // half way between 0x40 and 0x80
+        assert_eq!(BlendMode::Add.blend(dst, src, 128), 0xff60_6060);
+        // half way between 0x40 and 0x00
+        assert_eq!(BlendMode::Xor.blend(dst, src, 128), 0xff20_2020);
+        assert_eq!(
+            BlendMode::Replace.blend(0x0000_0000, 0xff80_8080, 128),
+            0x8040_4040
+        );
+        assert_eq!(fade(200, 0.5), 100);
+        assert_eq!(fade(200, 2.0), 200);
+    }
+}
@@ -1,27 +1,23 @@
+use crate::blend::alpha;
 use crate::canvas::Canvas;
 
+/// How opaque each pixel of a canvas is when blitted, 0 for skipped through
+/// 255 for copied over whatever is underneath.
 pub struct BlitMap {
-    pub map: Vec<bool>,
+    pub map: Vec<u8>,
 }
 
 impl BlitMap {
     pub fn from_canvas(canvas: &Canvas) -> BlitMap {
         return BlitMap {
-            map: vec![true; canvas.width * canvas.height],
+            map: vec![255; canvas.width * canvas.height],
         };
     }
 
+    /// Use the alpha channel of each pixel, as loaded by `load_image`.
     pub fn from_canvas_with_alpha(canvas: &Canvas) -> BlitMap {
-        let mut b = vec![false; canvas.width * canvas.height];
-        for x in 0..canvas.width {
-            for y in 0..canvas.height {
-                let b_index = (y * canvas.width) + x;
-                let cur_index = b_index;
-                if canvas.pixels[cur_index + 3] > 0 {
-                    b[b_index] = true;
-                }
-            }
+        BlitMap {
+            map: canvas.pixels.iter().map(|p| alpha(*p)).collect(),
         }
-        BlitMap { map: b }
     }
 }
@@ -0,0 +1,264 @@
+use crate::canvas::Canvas;
+use crate::point::*;
+use crate::swipe::*;
+use std::error::Error;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// An affine transform from touch coordinates to screen coordinates:
+///
+/// ```text
+/// x' = a * x + b * y + c
+/// y' = d * x + e * y + f
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Calibration {
+    pub matrix: [f32; 6],
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration::identity()
+    }
+}
+
+impl Calibration {
+    pub fn identity() -> Self {
+        Calibration {
+            matrix: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+        }
+    }
+
+    /// Fit a transform that takes each `touched` point as close as possible to
+    /// its `target` on screen, using least squares over three or more pairs.
+    pub fn from_points(
+        targets: &[(isize, isize)],
+        touched: &[(isize, isize)],
+    ) -> Result<Self, Box<dyn Error>> {
+        if targets.len() != touched.len() || targets.len() < 3 {
+            return Err("calibration needs at least three pairs of points".into());
+        }
+        // normal equations of the least squares fit, shared by both rows
+        let mut m = [[0f64; 3]; 3];
+        let mut rx = [0f64; 3];
+        let mut ry = [0f64; 3];
+        for (&(tx, ty), &(x, y)) in targets.iter().zip(touched.iter()) {
+            let v = [x as f64, y as f64, 1.0];
+            for i in 0..3 {
+                for j in 0..3 {
+                    m[i][j] += v[i] * v[j];
+                }
+                rx[i] += v[i] * tx as f64;
+                ry[i] += v[i] * ty as f64;
+            }
+        }
+        let (a, b, c) = solve3(&m, &rx).ok_or("calibration points are in a line")?;
+        let (d, e, f) = solve3(&m, &ry).ok_or("calibration points are in a line")?;
+        Ok(Calibration {
+            matrix: [a as f32, b as f32, c as f32, d as f32, e as f32, f as f32],
+        })
+    }
+
+    pub fn apply(&self, point: &Point) -> Point {
+        let [a, b, c, d, e, f] = self.matrix;
+        let (x, y) = (point.x as f32, point.y as f32);
+        Point {
+            time: point.time,
+            x: (a * x + b * y + c).round() as isize,
+            y: (d * x + e * y + f).round() as isize,
+        }
+    }
+
+    /// Where calibrations are saved by default: `$CONIFER_CALIBRATION` if set,
+    /// otherwise `~/.config/conifer/calibration`.
+    pub fn default_path() -> PathBuf {
+        if let Some(path) = std::env::var_os("CONIFER_CALIBRATION") {
+            return PathBuf::from(path);
+        }
+        let home = std::env::var_os("HOME").unwrap_or_else(|| ".".into());
+        PathBuf::from(home)
+            .join(".config")
+            .join("conifer")
+            .join("calibration")
+    }
+
+    /// Load the calibration at `default_path`, or the identity if none was saved.
+    pub fn load_default() -> Result<Self, Box<dyn Error>> {
+        let path = Calibration::default_path();
+        if !path.exists() {
+            return Ok(Calibration::identity());
+        }
+        Calibration::load(path)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let values = text
+            .split_whitespace()
+            .map(|v| v.parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()?;
+        if values.len() != 6 {
+            return Err("calibration file should contain six numbers".into());
+        }
+        let mut matrix = [0.0; 6];
+        matrix.copy_from_slice(&values);
+        Ok(Calibration { matrix })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let [a, b, c, d, e, f] = self.matrix;
+        std::fs::write(path, format!("{} {} {}\n{} {} {}\n", a, b, c, d, e, f))?;
+        Ok(())
+    }
+}
+
+// solve m * x = r with Cramer's rule
+fn solve3(m: &[[f64; 3]; 3], r: &[f64; 3]) -> Option<(f64, f64, f64)> {
+    let det = |m: &[[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let d = det(m);
+    if d.abs() < 1e-9 {
+        return None;
+    }
+    let column = |c: usize| {
+        let mut mc = *m;
+        for (row, value) in mc.iter_mut().zip(r.iter()) {
+            row[c] = *value;
+        }
+        det(&mc) / d
+    };
+    Some((column(0), column(1), column(2)))
+}
+
+/// Walks the user through tapping a crosshair at each target in turn. Feed it
+/// swipes from an uncalibrated `Config` and draw it every frame.
+#[derive(Debug, Clone)]
+pub struct Calibrator {
+    targets: Vec<(isize, isize)>,
+    touched: Vec<(isize, isize)>,
+}
+
+impl Calibrator {
+    /// Five targets, one near each corner and one in the middle of the screen.
+    pub fn new(width: usize, height: usize) -> Self {
+        let (w, h) = (width as isize, height as isize);
+        let (dx, dy) = (w / 8, h / 8);
+        Calibrator::with_targets(vec![
+            (dx, dy),
+            (w - 1 - dx, dy),
+            (w - 1 - dx, h - 1 - dy),
+            (dx, h - 1 - dy),
+            (w / 2, h / 2),
+        ])
+    }
+
+    pub fn with_targets(targets: Vec<(isize, isize)>) -> Self {
+        Calibrator {
+            targets,
+            touched: vec![],
+        }
+    }
+
+    pub fn current_target(&self) -> Option<(isize, isize)> {
+        self.targets.get(self.touched.len()).cloned()
+    }
+
+    /// Record a finished swipe as a tap on the current target. Returns the
+    /// calibration once every target has been tapped.
+    pub fn update(&mut self, swipe: &Swipe) -> Option<Calibration> {
+        if !swipe.finished || self.current_target().is_none() {
+            return None;
+        }
+        let n = swipe.points.len() as isize;
+        let x = swipe.points.iter().map(|p| p.x).sum::<isize>() / n;
+        let y = swipe.points.iter().map(|p| p.y).sum::<isize>() / n;
+        self.touched.push((x, y));
+        if self.touched.len() < self.targets.len() {
+            return None;
+        }
+        match Calibration::from_points(&self.targets, &self.touched) {
+            Ok(calibration) => Some(calibration),
+            Err(_) => {
+                // the taps were unusable, start over
+                self.touched.clear();
+                None
+            }
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, color: u32) {
+        if let Some((x, y)) = self.current_target() {
+            let size = 10;
+            for i in -size..=size {
+                for (px, py) in [(x + i, y), (x, y + i)].iter() {
+                    if *px >= 0
+                        && *py >= 0
+                        && (*px as usize) < canvas.width
+                        && (*py as usize) < canvas.height
+                    {
+                        canvas.set_pixel(*px as usize, *py as usize, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tap(x: isize, y: isize) -> Swipe {
+        let mut s = Swipe::new(Point {
+            time: Timeval(0, 0),
+            x,
+            y,
+        });
+        s.end();
+        s
+    }
+
+    #[test]
+    fn fits_skewed_panel() {
+        // the panel reads half scale, slightly sheared and offset
+        let skew = |(x, y): (isize, isize)| (x / 2 + y / 10 + 7, y / 2 - 3);
+        let mut calibrator = Calibrator::new(800, 480);
+        let mut result = None;
+        while let Some(target) = calibrator.current_target() {
+            let (x, y) = skew(target);
+            result = calibrator.update(&tap(x, y));
+        }
+        let calibration = result.expect("calibration should be computed");
+        let corrected = calibration.apply(&Point {
+            time: Timeval(0, 0),
+            x: skew((400, 240)).0,
+            y: skew((400, 240)).1,
+        });
+        assert!((corrected.x - 400).abs() <= 2);
+        assert!((corrected.y - 240).abs() <= 2);
+    }
+
+    #[test]
+    fn rejects_points_in_a_line() {
+        let line = [(0, 0), (1, 1), (2, 2)];
+        assert!(Calibration::from_points(&line, &line).is_err());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("conifer-calibration-save-{}", std::process::id()));
+        let calibration = Calibration {
+            matrix: [1.5, 0.25, -3.0, 0.0, 2.0, 10.0],
+        };
+        calibration.save(&path).unwrap();
+        assert_eq!(Calibration::load(&path).unwrap(), calibration);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
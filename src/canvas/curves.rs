@@ -0,0 +1,186 @@
+use crate::canvas::{Canvas, FillRule, Stroke};
+use crate::ink::InkPoint;
+
+// how far, in pixels, the straight pieces of a flattened curve may stray from it
+const TOLERANCE: f32 = 0.25;
+
+fn lerp(a: (f32, f32), b: (f32, f32), t: f32) -> (f32, f32) {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+fn length(v: (f32, f32)) -> f32 {
+    (v.0 * v.0 + v.1 * v.1).sqrt()
+}
+
+/// Points along the quadratic Bezier curve from `p0` to `p1` pulled towards
+/// `c`, close enough together that straight lines between them look curved.
+pub fn flatten_quadratic(p0: (f32, f32), c: (f32, f32), p1: (f32, f32)) -> Vec<(f32, f32)> {
+    // a quadratic strays at most a quarter of this from the chord
+    let dd = length((p0.0 - 2.0 * c.0 + p1.0, p0.1 - 2.0 * c.1 + p1.1));
+    let n = ((dd / (4.0 * TOLERANCE)).sqrt().ceil() as usize).max(1);
+    (0..=n)
+        .map(|i| {
+            let t = i as f32 / n as f32;
+            lerp(lerp(p0, c, t), lerp(c, p1, t), t)
+        })
+        .collect()
+}
+
+/// Points along the cubic Bezier curve from `p0` to `p1` with control points
+/// `c0` and `c1`, as `flatten_quadratic`.
+pub fn flatten_cubic(
+    p0: (f32, f32),
+    c0: (f32, f32),
+    c1: (f32, f32),
+    p1: (f32, f32),
+) -> Vec<(f32, f32)> {
+    let dd = length((p0.0 - 2.0 * c0.0 + c1.0, p0.1 - 2.0 * c0.1 + c1.1))
+        .max(length((c0.0 - 2.0 * c1.0 + p1.0, c0.1 - 2.0 * c1.1 + p1.1)));
+    let n = ((3.0 * dd / (4.0 * TOLERANCE)).sqrt().ceil() as usize).max(1);
+    (0..=n)
+        .map(|i| {
+            let t = i as f32 / n as f32;
+            let (a, b, c) = (lerp(p0, c0, t), lerp(c0, c1, t), lerp(c1, p1, t));
+            lerp(lerp(a, b, t), lerp(b, c, t), t)
+        })
+        .collect()
+}
+
+impl Canvas {
+    /// A quadratic Bezier curve from `p0` to `p1` pulled towards `c`, where
+    /// whole coordinates are pixel centers.
+    pub fn draw_quadratic(
+        &mut self,
+        p0: (f32, f32),
+        c: (f32, f32),
+        p1: (f32, f32),
+        stroke: &Stroke,
+        color: u32,
+    ) {
+        self.draw_polyline(&flatten_quadratic(p0, c, p1), stroke, color);
+    }
+
+    /// A cubic Bezier curve from `p0` to `p1` with control points `c0` and
+    /// `c1`, where whole coordinates are pixel centers.
+    pub fn draw_cubic(
+        &mut self,
+        p0: (f32, f32),
+        c0: (f32, f32),
+        c1: (f32, f32),
+        p1: (f32, f32),
+        stroke: &Stroke,
+        color: u32,
+    ) {
+        self.draw_polyline(&flatten_cubic(p0, c0, c1, p1), stroke, color);
+    }
+
+    /// A round ended stroke through `path` whose width follows each point's,
+    /// such as `Ink::path` makes from a swipe.
+    pub fn draw_ink(&mut self, path: &[InkPoint], color: u32) {
+        // fill_path puts pixel centers at halves
+        let center = |p: &InkPoint| (p.x + 0.5, p.y + 0.5);
+        for p in path {
+            self.fill_disc(center(p), p.width / 2.0, color);
+        }
+        for pair in path.windows(2) {
+            let (a, b) = (center(&pair[0]), center(&pair[1]));
+            let d = length((b.0 - a.0, b.1 - a.1));
+            if d == 0.0 {
+                continue;
+            }
+            let (nx, ny) = ((a.1 - b.1) / d, (b.0 - a.0) / d);
+            let (ha, hb) = (pair[0].width / 2.0, pair[1].width / 2.0);
+            self.fill_path(
+                &[
+                    (a.0 + nx * ha, a.1 + ny * ha),
+                    (b.0 + nx * hb, b.1 + ny * hb),
+                    (b.0 - nx * hb, b.1 - ny * hb),
+                    (a.0 - nx * ha, a.1 - ny * ha),
+                ],
+                FillRule::NonZero,
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::assert_golden;
+
+    #[test]
+    fn flattened_curves_stay_close() {
+        let points = flatten_quadratic((0.0, 0.0), (50.0, 100.0), (100.0, 0.0));
+        assert_eq!(points[0], (0.0, 0.0));
+        assert_eq!(*points.last().unwrap(), (100.0, 0.0));
+        // the curve peaks half way to the control point
+        let peak = points.iter().map(|p| p.1).fold(0.0, f32::max);
+        assert!((peak - 50.0).abs() < TOLERANCE);
+        assert!(points.len() > 10);
+
+        // a straight cubic needs no pieces beyond the line itself
+        let line = flatten_cubic((0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0));
+        assert_eq!(line, vec![(0.0, 0.0), (3.0, 3.0)]);
+    }
+
+    #[test]
+    fn ink_widths_vary() {
+        let black = 0xff00_0000;
+        let mut canvas = Canvas::new(20, 9, &[black; 180]);
+        let path = [
+            InkPoint {
+                x: 4.0,
+                y: 4.0,
+                width: 7.0,
+            },
+            InkPoint {
+                x: 16.0,
+                y: 4.0,
+                width: 1.0,
+            },
+        ];
+        canvas.draw_ink(&path, !0);
+        let column = |x: usize| {
+            (0..9)
+                .filter(|y| canvas.pixels[y * 20 + x] != black)
+                .count()
+        };
+        assert_eq!(column(4), 7);
+        assert_eq!(column(16), 1);
+        assert!(column(10) > 1 && column(10) < 7);
+    }
+
+    #[test]
+    fn curves_match_golden() {
+        let mut canvas = Canvas::new(64, 48, &[0xff00_0000; 64 * 48]);
+        let stroke = Stroke::new(2.0);
+        canvas.draw_quadratic(
+            (2.0, 20.0),
+            (16.0, -10.0),
+            (30.0, 20.0),
+            &stroke,
+            0xffe0_4040,
+        );
+        canvas.draw_cubic(
+            (34.0, 4.0),
+            (70.0, 4.0),
+            (26.0, 22.0),
+            (62.0, 22.0),
+            &stroke,
+            0xff40_c040,
+        );
+        let ink: Vec<InkPoint> = (0..=10)
+            .map(|i| {
+                let t = i as f32 / 10.0;
+                InkPoint {
+                    x: 4.0 + t * 56.0,
+                    y: 36.0 + (t * 6.0).sin() * 6.0,
+                    width: 1.0 + t * 5.0,
+                }
+            })
+            .collect();
+        canvas.draw_ink(&ink, 0xffff_ffff);
+        assert_golden("curves", &canvas);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::canvas::Canvas;
+use crate::rect::Rect;
+
+// past this many separate areas they're merged into one covering them all,
+// which is cheaper to track and usually little more to copy
+const MAX_DAMAGE: usize = 16;
+
+impl Canvas {
+    /// Record that an area has changed and needs pushing to the screen by
+    /// `RunResponse::DrawDamaged`. Drawing methods do this themselves, but
+    /// writes straight to `pixels` need to call it.
+    pub fn damage(&mut self, x: isize, y: isize, width: usize, height: usize) {
+        if let Some(rect) = Rect::clipped(x, y, width, height, self.width, self.height) {
+            self.add_damage(rect);
+        }
+    }
+
+    /// Record that the whole canvas has changed.
+    pub fn damage_all(&mut self) {
+        self.damage.clear();
+        if self.width > 0 && self.height > 0 {
+            self.damage.push(Rect::new(0, 0, self.width, self.height));
+        }
+    }
+
+    /// The areas changed since the damage was last taken, none overlapping.
+    pub fn damaged(&self) -> &[Rect] {
+        &self.damage
+    }
+
+    pub fn take_damage(&mut self) -> Vec<Rect> {
+        std::mem::take(&mut self.damage)
+    }
+
+    pub(super) fn damage_pixel(&mut self, x: usize, y: usize) {
+        let pixel = Rect::new(x, y, 1, 1);
+        // drawing tends to stay in the area it was just in
+        if self.damage.last().is_some_and(|r| r.contains(&pixel)) {
+            return;
+        }
+        self.add_damage(pixel);
+    }
+
+    fn add_damage(&mut self, mut rect: Rect) {
+        // grow the new area over any it touches until none are left to absorb
+        let mut i = 0;
+        while i < self.damage.len() {
+            if self.damage[i].touches(&rect) {
+                rect = rect.union(&self.damage.swap_remove(i));
+                i = 0;
+            } else {
+                i += 1;
+            }
+        }
+        self.damage.push(rect);
+        if self.damage.len() > MAX_DAMAGE {
+            let all = self.damage.iter().fold(rect, |a, r| a.union(r));
+            self.damage.clear();
+            self.damage.push(all);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::{Point, Timeval};
+
+    fn blank(width: usize, height: usize) -> Canvas {
+        Canvas::new(width, height, &vec![0; width * height])
+    }
+
+    #[test]
+    fn drawing_records_damage() {
+        let mut canvas = blank(20, 20);
+        assert!(canvas.damaged().is_empty());
+        canvas.fill_rect(2, 2, 3, 3, !0);
+        let point = |x, y| Point {
+            time: Timeval(0, 0),
+            x,
+            y,
+        };
+        canvas.plot_line(point(10, 10), point(14, 12), !0);
+        canvas.set_pixel(10, 0, !0);
+        let mut damage = canvas.take_damage();
+        damage.sort_by_key(|r| (r.y, r.x));
+        assert_eq!(
+            damage,
+            vec![
+                Rect::new(10, 0, 1, 1),
+                Rect::new(2, 2, 3, 3),
+                Rect::new(10, 10, 5, 3),
+            ]
+        );
+        assert!(canvas.damaged().is_empty());
+    }
+
+    #[test]
+    fn damage_is_clipped_and_merged() {
+        let mut canvas = blank(10, 10);
+        canvas.damage(-5, -5, 8, 8);
+        canvas.damage(3, 0, 2, 2);
+        assert_eq!(canvas.damaged(), &[Rect::new(0, 0, 5, 3)]);
+        canvas.damage(20, 20, 5, 5);
+        assert_eq!(canvas.damaged().len(), 1);
+
+        // too many scattered areas collapse into one
+        let mut canvas = blank(30, 30);
+        for i in 0..MAX_DAMAGE {
+            canvas.set_pixel(i % 8 * 3, i / 8 * 3, !0);
+        }
+        assert_eq!(canvas.damaged().len(), MAX_DAMAGE);
+        canvas.set_pixel(0, 6, !0);
+        assert_eq!(canvas.damaged(), &[Rect::new(0, 0, 22, 7)]);
+    }
+}
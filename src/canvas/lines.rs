@@ -0,0 +1,503 @@
+use crate::canvas::{Canvas, FillRule};
+
+// how far a miter join may reach past the stroke's half width before it is
+// beveled instead, as in SVG
+const MITER_LIMIT: f32 = 4.0;
+
+/// How the ends of a thick line are drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineCap {
+    /// Stop flat at the end point.
+    Butt,
+    /// Finish with a half circle around the end point.
+    Round,
+    /// Stop flat half the stroke's width past the end point.
+    Square,
+}
+
+/// How the segments of a thick polyline meet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineJoin {
+    /// Extend the outer edges until they meet, beveling very sharp corners.
+    Miter,
+    /// Round the outer corner off.
+    Round,
+    /// Cut the outer corner off straight.
+    Bevel,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Stroke::new(1.0)
+    }
+}
+
+impl Stroke {
+    pub fn new(width: f32) -> Self {
+        Stroke {
+            width,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+        }
+    }
+}
+
+// Cohen-Sutherland region codes
+const INSIDE: u8 = 0;
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const TOP: u8 = 4;
+const BOTTOM: u8 = 8;
+
+/// Clip the line from `p0` to `p1` to the rectangle from `min` to `max`
+/// inclusive, or `None` if none of it is inside.
+pub fn clip_line(
+    mut p0: (f32, f32),
+    mut p1: (f32, f32),
+    min: (f32, f32),
+    max: (f32, f32),
+) -> Option<((f32, f32), (f32, f32))> {
+    let code = |(x, y): (f32, f32)| {
+        let mut c = INSIDE;
+        if x < min.0 {
+            c |= LEFT;
+        } else if x > max.0 {
+            c |= RIGHT;
+        }
+        if y < min.1 {
+            c |= TOP;
+        } else if y > max.1 {
+            c |= BOTTOM;
+        }
+        c
+    };
+    let (mut c0, mut c1) = (code(p0), code(p1));
+    loop {
+        if c0 | c1 == INSIDE {
+            return Some((p0, p1));
+        }
+        if c0 & c1 != INSIDE {
+            return None;
+        }
+        // move whichever end is outside onto the edge it's beyond
+        let outside = if c0 != INSIDE { c0 } else { c1 };
+        let (x0, y0) = p0;
+        let (x1, y1) = p1;
+        let p = if outside & TOP != 0 {
+            (x0 + (x1 - x0) * (min.1 - y0) / (y1 - y0), min.1)
+        } else if outside & BOTTOM != 0 {
+            (x0 + (x1 - x0) * (max.1 - y0) / (y1 - y0), max.1)
+        } else if outside & RIGHT != 0 {
+            (max.0, y0 + (y1 - y0) * (max.0 - x0) / (x1 - x0))
+        } else {
+            (min.0, y0 + (y1 - y0) * (min.0 - x0) / (x1 - x0))
+        };
+        if outside == c0 {
+            p0 = p;
+            c0 = code(p0);
+        } else {
+            p1 = p;
+            c1 = code(p1);
+        }
+    }
+}
+
+fn fract(v: f32) -> f32 {
+    v - v.floor()
+}
+
+impl Canvas {
+    // a one pixel wide line from (x0, y0) to (x1, y1), clipped to the canvas
+    pub(super) fn segment(
+        &mut self,
+        (x0, y0): (isize, isize),
+        (x1, y1): (isize, isize),
+        color: u32,
+    ) {
+        let max = (self.width as f32 - 1.0, self.height as f32 - 1.0);
+        let clipped = clip_line(
+            (x0 as f32, y0 as f32),
+            (x1 as f32, y1 as f32),
+            (0.0, 0.0),
+            max,
+        );
+        let ((x0, y0), (x1, y1)) = match clipped {
+            Some(((x0, y0), (x1, y1))) => (
+                (x0.round() as isize, y0.round() as isize),
+                (x1.round() as isize, y1.round() as isize),
+            ),
+            None => return,
+        };
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let (mut x, mut y) = (x0, y0);
+        let mut err = dx + dy;
+        loop {
+            self.plot(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// A one pixel wide antialiased line using Xiaolin Wu's algorithm, where
+    /// whole coordinates are pixel centers.
+    pub fn draw_line_aa(&mut self, from: (f32, f32), to: (f32, f32), color: u32) {
+        // keep a pixel of margin so partially covered edge pixels still draw
+        let max = (self.width as f32, self.height as f32);
+        let ((mut x0, mut y0), (mut x1, mut y1)) = match clip_line(from, to, (-1.0, -1.0), max) {
+            Some(clipped) => clipped,
+            None => return,
+        };
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            std::mem::swap(&mut x0, &mut y0);
+            std::mem::swap(&mut x1, &mut y1);
+        }
+        if x0 > x1 {
+            std::mem::swap(&mut x0, &mut x1);
+            std::mem::swap(&mut y0, &mut y1);
+        }
+        let mut plot = |x: f32, y: f32, coverage: f32| {
+            let (x, y) = if steep { (y, x) } else { (x, y) };
+            let coverage = (coverage * 255.0).round().clamp(0.0, 255.0) as u8;
+            self.blend_pixel(x as isize, y as isize, color, coverage);
+        };
+        let dx = x1 - x0;
+        let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+        let x_start = x0.round();
+        let y_start = y0 + gradient * (x_start - x0);
+        let gap = 1.0 - fract(x0 + 0.5);
+        plot(x_start, y_start.floor(), (1.0 - fract(y_start)) * gap);
+        plot(x_start, y_start.floor() + 1.0, fract(y_start) * gap);
+
+        let x_end = x1.round();
+        let y_end = y1 + gradient * (x_end - x1);
+        let gap = fract(x1 + 0.5);
+        plot(x_end, y_end.floor(), (1.0 - fract(y_end)) * gap);
+        plot(x_end, y_end.floor() + 1.0, fract(y_end) * gap);
+
+        let mut y = y_start + gradient;
+        let mut x = x_start + 1.0;
+        while x < x_end {
+            plot(x, y.floor(), 1.0 - fract(y));
+            plot(x, y.floor() + 1.0, fract(y));
+            y += gradient;
+            x += 1.0;
+        }
+    }
+
+    /// A line `stroke.width` pixels wide, where whole coordinates are pixel
+    /// centers.
+    pub fn draw_thick_line(
+        &mut self,
+        from: (f32, f32),
+        to: (f32, f32),
+        stroke: &Stroke,
+        color: u32,
+    ) {
+        self.draw_polyline(&[from, to], stroke, color);
+    }
+
+    /// Connected thick lines through `points`, joined as `stroke.join` says.
+    pub fn draw_polyline(&mut self, points: &[(f32, f32)], stroke: &Stroke, color: u32) {
+        // move to the coordinates fill_path uses, where pixel centers are at halves
+        let points: Vec<(f32, f32)> = points.iter().map(|&(x, y)| (x + 0.5, y + 0.5)).collect();
+        let half = stroke.width / 2.0;
+        if points.is_empty() || half <= 0.0 {
+            return;
+        }
+        let direction = |a: (f32, f32), b: (f32, f32)| {
+            let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+            let length = (dx * dx + dy * dy).sqrt();
+            if length == 0.0 {
+                None
+            } else {
+                Some((dx / length, dy / length))
+            }
+        };
+        let mut segments = vec![];
+        for pair in points.windows(2) {
+            if let Some(d) = direction(pair[0], pair[1]) {
+                segments.push((pair[0], pair[1], d));
+            }
+        }
+        if segments.is_empty() {
+            // a dot
+            if stroke.cap != LineCap::Butt {
+                let p = points[0];
+                match stroke.cap {
+                    LineCap::Round => self.fill_disc(p, half, color),
+                    _ => self.fill_path(
+                        &[
+                            (p.0 - half, p.1 - half),
+                            (p.0 + half, p.1 - half),
+                            (p.0 + half, p.1 + half),
+                            (p.0 - half, p.1 + half),
+                        ],
+                        FillRule::NonZero,
+                        color,
+                    ),
+                }
+            }
+            return;
+        }
+        let last = segments.len() - 1;
+        for (i, &(a, b, (dx, dy))) in segments.iter().enumerate() {
+            let (nx, ny) = (-dy * half, dx * half);
+            let (mut a, mut b) = (a, b);
+            if stroke.cap == LineCap::Square {
+                if i == 0 {
+                    a = (a.0 - dx * half, a.1 - dy * half);
+                }
+                if i == last {
+                    b = (b.0 + dx * half, b.1 + dy * half);
+                }
+            }
+            self.fill_path(
+                &[
+                    (a.0 + nx, a.1 + ny),
+                    (b.0 + nx, b.1 + ny),
+                    (b.0 - nx, b.1 - ny),
+                    (a.0 - nx, a.1 - ny),
+                ],
+                FillRule::NonZero,
+                color,
+            );
+        }
+        if stroke.cap == LineCap::Round {
+            self.fill_disc(segments[0].0, half, color);
+            self.fill_disc(segments[last].1, half, color);
+        }
+        for pair in segments.windows(2) {
+            let (_, v, d1) = pair[0];
+            let (_, _, d2) = pair[1];
+            self.join(v, d1, d2, half, stroke.join, color);
+        }
+    }
+
+    // fill the outside corner where a segment heading d1 meets one heading d2 at v
+    fn join(
+        &mut self,
+        v: (f32, f32),
+        d1: (f32, f32),
+        d2: (f32, f32),
+        half: f32,
+        join: LineJoin,
+        color: u32,
+    ) {
+        let cross = d1.0 * d2.1 - d1.1 * d2.0;
+        if cross == 0.0 {
+            return;
+        }
+        if join == LineJoin::Round {
+            self.fill_disc(v, half, color);
+            return;
+        }
+        // the outer side is the one the path turns away from
+        let side = if cross > 0.0 { -half } else { half };
+        let n1 = (-d1.1 * side, d1.0 * side);
+        let n2 = (-d2.1 * side, d2.0 * side);
+        let c1 = (v.0 + n1.0, v.1 + n1.1);
+        let c2 = (v.0 + n2.0, v.1 + n2.1);
+        let (mx, my) = (n1.0 + n2.0, n1.1 + n2.1);
+        let m = (mx * mx + my * my).sqrt();
+        // the miter tip sits along the bisector of the normals, scaled so it
+        // reaches both outer edges
+        let cos = m / (2.0 * half);
+        if join == LineJoin::Miter && cos > 0.0 && 1.0 / cos <= MITER_LIMIT {
+            let reach = half / cos;
+            let tip = (v.0 + mx / m * reach, v.1 + my / m * reach);
+            self.fill_path(&[v, c1, tip, c2], FillRule::NonZero, color);
+        } else {
+            self.fill_path(&[v, c1, c2], FillRule::NonZero, color);
+        }
+    }
+
+    // fill the pixels whose centers lie within radius of center, in fill_path
+    // coordinates
+    pub(super) fn fill_disc(&mut self, center: (f32, f32), radius: f32, color: u32) {
+        let first = ((center.1 - radius).floor() as isize).max(0);
+        let last = ((center.1 + radius).ceil() as isize).min(self.height as isize - 1);
+        for y in first..=last {
+            let dy = y as f32 + 0.5 - center.1;
+            if dy.abs() > radius {
+                continue;
+            }
+            let dx = (radius * radius - dy * dy).sqrt();
+            let start = (center.0 - dx - 0.5).ceil() as isize;
+            let end = (center.0 + dx - 0.5).floor() as isize;
+            self.fill_span(y, start, end, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::assert_golden;
+    use crate::point::{Point, Timeval};
+
+    const W: u32 = 0xffff_ffff;
+    const B: u32 = 0xff00_0000;
+
+    fn rows(canvas: &Canvas) -> Vec<String> {
+        canvas
+            .pixels
+            .chunks(canvas.width)
+            .map(|row| {
+                row.iter()
+                    .map(|p| if *p == B { '.' } else { '#' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn point(x: isize, y: isize) -> Point {
+        Point {
+            time: Timeval(0, 0),
+            x,
+            y,
+        }
+    }
+
+    #[test]
+    fn clips_lines_off_the_canvas() {
+        let mut canvas = Canvas::new(4, 3, &[B; 12]);
+        canvas.plot_line(point(-5, 1), point(10, 1), W);
+        canvas.plot_line(point(-10, -10), point(-1, 5), W);
+        canvas.plot_line(point(2, -3), point(2, 0), W);
+        assert_eq!(rows(&canvas), vec!["..#.", "####", "...."]);
+        assert_eq!(
+            clip_line((-2.0, 0.0), (2.0, 4.0), (0.0, 0.0), (10.0, 10.0)),
+            Some(((0.0, 2.0), (2.0, 4.0)))
+        );
+        assert_eq!(
+            clip_line((-2.0, 0.0), (-1.0, 4.0), (0.0, 0.0), (10.0, 10.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn antialiased_line_splits_coverage() {
+        let mut canvas = Canvas::new(8, 4, &[B; 32]);
+        canvas.draw_line_aa((0.0, 1.5), (7.0, 1.5), W);
+        // half way between two rows, each gets half the color
+        for x in 1..7 {
+            assert_eq!(canvas.pixels[8 + x], 0xff80_8080);
+            assert_eq!(canvas.pixels[16 + x], 0xff80_8080);
+        }
+        assert!(canvas.pixels[..8].iter().all(|p| *p == B));
+
+        let mut canvas = Canvas::new(4, 4, &[B; 16]);
+        canvas.draw_line_aa((-3.0, 2.0), (10.0, 2.0), W);
+        assert_eq!(&canvas.pixels[8..12], &[W; 4]);
+        assert!(canvas.pixels[12..].iter().all(|p| *p == B));
+    }
+
+    #[test]
+    fn thick_line_caps() {
+        let mut stroke = Stroke::new(3.0);
+        let mut canvas = Canvas::new(10, 5, &[B; 50]);
+        canvas.draw_thick_line((2.0, 2.0), (7.0, 2.0), &stroke, W);
+        assert_eq!(
+            rows(&canvas),
+            vec![
+                "..........",
+                "..#####...",
+                "..#####...",
+                "..#####...",
+                ".........."
+            ]
+        );
+
+        stroke.cap = LineCap::Square;
+        let mut canvas = Canvas::new(10, 5, &[B; 50]);
+        canvas.draw_thick_line((2.0, 2.0), (7.0, 2.0), &stroke, W);
+        assert_eq!(rows(&canvas)[2], ".########.");
+
+        // round caps only differ from square ones once the stroke is wide
+        let stroke = Stroke {
+            width: 5.0,
+            cap: LineCap::Round,
+            join: LineJoin::Miter,
+        };
+        let mut canvas = Canvas::new(11, 5, &[B; 55]);
+        canvas.draw_thick_line((3.0, 2.0), (7.0, 2.0), &stroke, W);
+        assert_eq!(
+            rows(&canvas),
+            vec![
+                "..#######..",
+                ".#########.",
+                ".#########.",
+                ".#########.",
+                "..#######.."
+            ]
+        );
+    }
+
+    #[test]
+    fn polyline_joins() {
+        let corner = [(1.0, 6.0), (1.0, 1.0), (6.0, 1.0)];
+        let mut stroke = Stroke::new(3.0);
+        let mut canvas = Canvas::new(8, 8, &[B; 64]);
+        canvas.draw_polyline(&corner, &stroke, W);
+        // the miter fills the outer corner square
+        assert_eq!(&rows(&canvas)[..2], &["######..", "######.."]);
+
+        stroke.join = LineJoin::Bevel;
+        let mut canvas = Canvas::new(8, 8, &[B; 64]);
+        canvas.draw_polyline(&corner, &stroke, W);
+        assert_eq!(rows(&canvas)[0], ".#####..");
+        assert_eq!(rows(&canvas)[1], "######..");
+    }
+
+    #[test]
+    fn lines_match_golden() {
+        let mut canvas = Canvas::new(64, 48, &[B; 64 * 48]);
+        canvas.plot_line(point(2, 2), point(30, 12), W);
+        canvas.draw_line_aa((34.0, 2.0), (62.0, 14.0), W);
+        let caps = [LineCap::Butt, LineCap::Round, LineCap::Square];
+        for (i, cap) in caps.iter().enumerate() {
+            let y = 20.0 + i as f32 * 8.0;
+            let stroke = Stroke {
+                cap: *cap,
+                ..Stroke::new(5.0)
+            };
+            canvas.draw_thick_line((6.0, y), (26.0, y), &stroke, 0xffe0_4040);
+        }
+        let joins = [LineJoin::Miter, LineJoin::Round, LineJoin::Bevel];
+        for (i, join) in joins.iter().enumerate() {
+            let x = 34.0 + i as f32 * 10.0;
+            let stroke = Stroke {
+                join: *join,
+                ..Stroke::new(3.0)
+            };
+            canvas.draw_polyline(
+                &[(x, 44.0), (x + 4.0, 26.0), (x + 8.0, 44.0)],
+                &stroke,
+                0xff40_c040,
+            );
+        }
+        assert_golden("lines", &canvas);
+    }
+}
@@ -0,0 +1,413 @@
+use crate::blend::{alpha, fade, source_over, BlendMode};
+use crate::blit_map::BlitMap;
+use crate::font::Font;
+use crate::point::Point;
+use crate::rect::Rect;
+use crate::util::color_from_rgb;
+use std::error::Error;
+use std::path::Path;
+
+mod curves;
+mod damage;
+mod lines;
+mod resize;
+mod shapes;
+mod transform;
+pub use curves::{flatten_cubic, flatten_quadratic};
+pub use lines::{clip_line, LineCap, LineJoin, Stroke};
+pub use resize::Filter;
+pub use shapes::FillRule;
+pub use transform::{Sampling, Transform};
+
+#[derive(Debug)]
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u32>,
+    // areas drawn to since the damage was last taken
+    damage: Vec<Rect>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize, pixels: &[u32]) -> Self {
+        Canvas {
+            pixels: pixels.to_owned(),
+            width,
+            height,
+            damage: vec![],
+        }
+    }
+
+    pub fn from_color(width: usize, height: usize, r: u8, g: u8, b: u8) -> Self {
+        let mut pixels: Vec<u32> = vec![];
+        for _ in 0..width {
+            for _ in 0..height {
+                pixels.push(color_from_rgb(r, g, b));
+            }
+        }
+        Canvas {
+            pixels: pixels.to_owned(),
+            width,
+            height,
+            damage: vec![],
+        }
+    }
+
+    pub fn get_pixel(&mut self, x: usize, y: usize) -> u32 {
+        let curr_index = y * self.width + x;
+        self.pixels[curr_index]
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: u32) {
+        let curr_index = y * self.width + x;
+        self.pixels[curr_index] = color;
+        self.damage_pixel(x, y);
+    }
+
+    /// Mix `color` into a pixel by how much of it is covered, 0 for none
+    /// through 255 for all of it, ignoring coordinates outside the canvas.
+    pub fn blend_pixel(&mut self, x: isize, y: isize, color: u32, coverage: u8) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            let index = y as usize * self.width + x as usize;
+            self.pixels[index] = blend_coverage(self.pixels[index], color, coverage);
+            self.damage_pixel(x as usize, y as usize);
+        }
+    }
+
+    /// Set a pixel, ignoring coordinates outside the canvas.
+    pub fn plot(&mut self, x: isize, y: isize, color: u32) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            self.pixels[y as usize * self.width + x as usize] = color;
+            self.damage_pixel(x as usize, y as usize);
+        }
+    }
+
+    /// Draw `canvas` onto this one at `x`, `y`, combining pixels with `mode`
+    /// by their alpha scaled by `opacity`, between 0.0 and 1.0.
+    pub fn draw_canvas(
+        &mut self,
+        canvas: &Canvas,
+        x: isize,
+        y: isize,
+        mode: BlendMode,
+        opacity: f32,
+    ) -> Result<(), &'static str> {
+        // TODO figure out if this matterns
+        //if self.bytespp != canvas.bytespp {
+        //    return Err("cannot draw canvas due to incompatible bits per pixel");
+        //}
+        // nothing to draw if it lies wholly off this canvas
+        let area = match Rect::clipped(x, y, canvas.width, canvas.height, self.width, self.height) {
+            Some(area) => area,
+            None => return Ok(()),
+        };
+        self.damage(x, y, canvas.width, canvas.height);
+        let len = area.width;
+        for ry in area.y..area.bottom() {
+            let cur_index = ry * self.width + area.x;
+            let r_index =
+                (ry as isize - y) as usize * canvas.width + (area.x as isize - x) as usize;
+            let dst = &mut self.pixels[cur_index..cur_index + len];
+            let src = &canvas.pixels[r_index..r_index + len];
+            if mode == BlendMode::Replace && opacity >= 1.0 {
+                dst.copy_from_slice(src);
+                continue;
+            }
+            for (dst, src) in dst.iter_mut().zip(src) {
+                let a = match mode {
+                    BlendMode::Replace => fade(255, opacity),
+                    _ => fade(alpha(*src), opacity),
+                };
+                *dst = mode.blend(*dst, *src, a);
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `draw_canvas`, mirroring `canvas` left to right with `flip_x`
+    /// and top to bottom with `flip_y`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_canvas_flipped(
+        &mut self,
+        canvas: &Canvas,
+        x: isize,
+        y: isize,
+        flip_x: bool,
+        flip_y: bool,
+        mode: BlendMode,
+        opacity: f32,
+    ) -> Result<(), &'static str> {
+        if !flip_x && !flip_y {
+            return self.draw_canvas(canvas, x, y, mode, opacity);
+        }
+        let start_y = isize::max(y, 0);
+        let end_y = isize::min(y + canvas.height as isize, self.height as isize);
+        let start_x = isize::max(x, 0);
+        let end_x = isize::min(x + canvas.width as isize, self.width as isize);
+        self.damage(x, y, canvas.width, canvas.height);
+        for ry in start_y..end_y {
+            let mut sy = (ry - y) as usize;
+            if flip_y {
+                sy = canvas.height - 1 - sy;
+            }
+            for rx in start_x..end_x {
+                let mut sx = (rx - x) as usize;
+                if flip_x {
+                    sx = canvas.width - 1 - sx;
+                }
+                let src = canvas.pixels[sy * canvas.width + sx];
+                let a = match mode {
+                    BlendMode::Replace => fade(255, opacity),
+                    _ => fade(alpha(src), opacity),
+                };
+                let index = ry as usize * self.width + rx as usize;
+                self.pixels[index] = mode.blend(self.pixels[index], src, a);
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw `canvas` onto this one at `x`, `y`, combining pixels with `mode`
+    /// by how opaque `blit_map` says each is, scaled by `opacity`.
+    pub fn blit_canvas(
+        &mut self,
+        canvas: &Canvas,
+        x: isize,
+        y: isize,
+        blit_map: &BlitMap,
+        mode: BlendMode,
+        opacity: f32,
+    ) -> Result<(), &'static str> {
+        // TODO figure out if this matterns
+        //if self.bytespp != canvas.bytespp {
+        //    return Err("cannot draw canvas due to incompatible bits per pixel");
+        //}
+        let start_y = isize::max(y, 0);
+        let end_y = isize::min(y + canvas.height as isize, self.height as isize);
+        let start_x = isize::max(x, 0);
+        let end_x = isize::min(x + canvas.width as isize, self.width as isize);
+        self.damage(x, y, canvas.width, canvas.height);
+        for ry in start_y..end_y {
+            for rx in start_x..end_x {
+                let b_index = ((ry - y) * canvas.width as isize + (rx - x)) as usize;
+                let cur_index = (ry * self.width as isize + rx) as usize;
+                self.pixels[cur_index] = mode.blend(
+                    self.pixels[cur_index],
+                    canvas.pixels[b_index],
+                    fade(blit_map.map[b_index], opacity),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn copy_from_canvas(&mut self, canvas: &Canvas) -> Result<(), Box<dyn Error>> {
+        if self.pixels.len() != canvas.pixels.len() {
+            return Err("cannot copy in canvas that isn't same size".into());
+        }
+        self.pixels.copy_from_slice(&canvas.pixels);
+        self.damage_all();
+        Ok(())
+    }
+
+    /// Write the canvas to an image file in the format its extension names,
+    /// such as PNG, BMP or PPM. Formats without an alpha channel drop it.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 4);
+        for p in self.pixels.iter() {
+            bytes.extend_from_slice(&[(p >> 16) as u8, (p >> 8) as u8, *p as u8, alpha(*p)]);
+        }
+        let image = image::RgbaImage::from_raw(self.width as u32, self.height as u32, bytes)
+            .ok_or("canvas has the wrong number of pixels for its size")?;
+        let image = image::DynamicImage::ImageRgba8(image);
+        match image::ImageFormat::from_path(&path)? {
+            image::ImageFormat::Png => image.save(path)?,
+            _ => image.to_rgb8().save(path)?,
+        }
+        Ok(())
+    }
+
+    /// A one pixel wide line, clipped to the canvas.
+    pub fn plot_line(&mut self, point0: Point, point1: Point, color: u32) {
+        self.segment((point0.x, point0.y), (point1.x, point1.y), color);
+    }
+
+    /// Draw `text` with its first line's top left corner at `x`, `y`. Each
+    /// newline starts a new line `font.line_height` further down.
+    pub fn draw_text(&mut self, x: isize, y: isize, text: &str, color: u32, font: &Font) {
+        for placed in font.layout(text) {
+            let glyph = &placed.glyph;
+            let left = x + placed.x;
+            let top = y + placed.y;
+            self.damage(left, top, glyph.width, glyph.height);
+            for gy in 0..glyph.height {
+                let py = top + gy as isize;
+                if py < 0 || py >= self.height as isize {
+                    continue;
+                }
+                for gx in 0..glyph.width {
+                    let px = left + gx as isize;
+                    if px < 0 || px >= self.width as isize {
+                        continue;
+                    }
+                    let coverage = glyph.coverage[gy * glyph.width + gx];
+                    if coverage == 0 {
+                        continue;
+                    }
+                    let index = py as usize * self.width + px as usize;
+                    self.pixels[index] = blend_coverage(self.pixels[index], color, coverage);
+                }
+            }
+        }
+    }
+}
+
+// mix color over dst by how much of the pixel is covered
+fn blend_coverage(dst: u32, color: u32, coverage: u8) -> u32 {
+    let a = (alpha(color) as u32 * coverage as u32 + 127) / 255;
+    source_over(dst, color, a as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::assert_golden;
+    use crate::util::load_image;
+
+    #[test]
+    fn draw_text_handles_newlines_and_clipping() {
+        let font = Font::default();
+        let white = color_from_rgb(255, 255, 255);
+        let mut canvas = Canvas::new(12, 20, &[0; 12 * 20]);
+        // the first column of each glyph is blank, so starting at -1 clips nothing visible
+        canvas.draw_text(-1, 0, "II\nl", white, &font);
+        let w = white;
+        assert_eq!(&canvas.pixels[..10], &[w, w, w, 0, 0, 0, w, w, w, 0]);
+        // the next line starts line_height rows further down
+        assert_eq!(&canvas.pixels[9 * 12..9 * 12 + 3], &[w, w, 0]);
+        assert!(canvas.pixels[8 * 12..9 * 12].iter().all(|p| *p == 0));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let canvas = Canvas::new(2, 1, &[0x80ff_0000, color_from_rgb(0, 0x80, 0xff)]);
+        let dir = std::env::temp_dir();
+        let png = dir.join(format!("conifer-canvas-{}.png", std::process::id()));
+        canvas.save(&png).unwrap();
+        assert_eq!(load_image(&png).unwrap().pixels, canvas.pixels);
+        std::fs::remove_file(&png).unwrap();
+
+        // no alpha in a PPM, so it comes back opaque
+        let ppm = dir.join(format!("conifer-canvas-{}.ppm", std::process::id()));
+        canvas.save(&ppm).unwrap();
+        assert_eq!(
+            load_image(&ppm).unwrap().pixels,
+            vec![0xffff_0000, color_from_rgb(0, 0x80, 0xff)]
+        );
+        std::fs::remove_file(&ppm).unwrap();
+        assert!(canvas.save(dir.join("conifer-canvas.unknown")).is_err());
+    }
+
+    #[test]
+    fn blit_canvas_blends_by_alpha() {
+        let black = color_from_rgb(0, 0, 0);
+        let mut canvas = Canvas::new(4, 1, &[black; 4]);
+        // opaque, half transparent, fully transparent white
+        let sprite = Canvas::new(3, 1, &[0xffff_ffff, 0x80ff_ffff, 0x00ff_ffff]);
+        let map = BlitMap::from_canvas_with_alpha(&sprite);
+        canvas
+            .blit_canvas(&sprite, 1, 0, &map, BlendMode::Normal, 1.0)
+            .unwrap();
+        assert_eq!(canvas.pixels, vec![black, 0xffff_ffff, 0xff80_8080, black]);
+
+        // ignoring alpha copies every pixel, clipped at the edge
+        let mut canvas = Canvas::new(2, 1, &[black; 2]);
+        canvas
+            .blit_canvas(
+                &sprite,
+                -1,
+                0,
+                &BlitMap::from_canvas(&sprite),
+                BlendMode::Normal,
+                1.0,
+            )
+            .unwrap();
+        assert_eq!(canvas.pixels, vec![0xffff_ffff, 0xffff_ffff]);
+    }
+
+    #[test]
+    fn draws_nothing_off_canvas() {
+        let black = color_from_rgb(0, 0, 0);
+        let sprite = Canvas::new(2, 2, &[0xffff_ffff; 4]);
+        let map = BlitMap::from_canvas(&sprite);
+        let mut canvas = Canvas::new(4, 2, &[black; 8]);
+        for (x, y) in [(10, 0), (-10, 0), (0, 5), (0, -5), (4, 0), (-2, 0)] {
+            for mode in [BlendMode::Replace, BlendMode::Normal] {
+                canvas.draw_canvas(&sprite, x, y, mode, 1.0).unwrap();
+                canvas
+                    .draw_canvas_flipped(&sprite, x, y, true, false, mode, 1.0)
+                    .unwrap();
+                canvas.blit_canvas(&sprite, x, y, &map, mode, 1.0).unwrap();
+            }
+        }
+        assert_eq!(canvas.pixels, vec![black; 8]);
+        assert!(canvas.damaged().is_empty());
+    }
+
+    #[test]
+    fn draw_canvas_blends_with_opacity() {
+        let grey = color_from_rgb(0x40, 0x40, 0x40);
+        let sprite = Canvas::new(2, 1, &[0xff40_4040, 0x8040_4040]);
+
+        let mut canvas = Canvas::new(3, 1, &[grey; 3]);
+        canvas
+            .draw_canvas(&sprite, 1, 0, BlendMode::Add, 1.0)
+            .unwrap();
+        // the second source pixel is half transparent, so adds half as much
+        assert_eq!(canvas.pixels, vec![grey, 0xff80_8080, 0xff60_6060]);
+
+        let mut canvas = Canvas::new(3, 1, &[grey; 3]);
+        canvas
+            .draw_canvas(&sprite, 0, 0, BlendMode::Add, 0.5)
+            .unwrap();
+        assert_eq!(canvas.pixels, vec![0xff60_6060, 0xff50_5050, grey]);
+
+        // replacing copies alpha too, and ignores it
+        let mut canvas = Canvas::new(2, 1, &[grey; 2]);
+        canvas
+            .draw_canvas(&sprite, 0, 0, BlendMode::Replace, 1.0)
+            .unwrap();
+        assert_eq!(canvas.pixels, sprite.pixels);
+    }
+
+    #[test]
+    fn text_and_blending_match_golden() {
+        let mut canvas = Canvas::new(64, 48, &[color_from_rgb(0x20, 0x40, 0x80); 64 * 48]);
+        canvas.draw_text(
+            2,
+            2,
+            "Hi!\nconifer",
+            color_from_rgb(255, 255, 255),
+            &Font::default(),
+        );
+        // a gradient square drawn with each mode
+        let pixels: Vec<u32> = (0..100)
+            .map(|i| ((i % 10) as u32 * 28) << 24 | 0x00e0_8020)
+            .collect();
+        let square = Canvas::new(10, 10, &pixels);
+        let modes = [
+            BlendMode::Replace,
+            BlendMode::Normal,
+            BlendMode::Add,
+            BlendMode::Multiply,
+            BlendMode::Screen,
+            BlendMode::Xor,
+        ];
+        for (i, mode) in modes.iter().enumerate() {
+            canvas
+                .draw_canvas(&square, 2 + i as isize * 10, 34, *mode, 0.8)
+                .unwrap();
+        }
+        assert_golden("text_and_blending", &canvas);
+    }
+}
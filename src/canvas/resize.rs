@@ -0,0 +1,273 @@
+use crate::blend::alpha;
+use crate::canvas::transform::bilinear;
+use crate::canvas::Canvas;
+
+/// How a resized canvas picks the colors of its new pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// The closest pixel, fastest and keeps hard edges.
+    Nearest,
+    /// A mix of the four closest pixels, smooth when enlarging.
+    Bilinear,
+    /// The average of every pixel each new one covers, best when shrinking.
+    Box,
+}
+
+impl Canvas {
+    /// A copy of this canvas scaled to `width` by `height`.
+    pub fn resize(&self, width: usize, height: usize, filter: Filter) -> Canvas {
+        if self.width == 0 || self.height == 0 {
+            return Canvas::new(width, height, &vec![0; width * height]);
+        }
+        let sx = self.width as f32 / width as f32;
+        let sy = self.height as f32 / height as f32;
+        let pixels = match filter {
+            Filter::Nearest => {
+                let mut pixels = Vec::with_capacity(width * height);
+                for y in 0..height {
+                    let v = usize::min(((y as f32 + 0.5) * sy) as usize, self.height - 1);
+                    for x in 0..width {
+                        let u = usize::min(((x as f32 + 0.5) * sx) as usize, self.width - 1);
+                        pixels.push(self.pixels[v * self.width + u]);
+                    }
+                }
+                pixels
+            }
+            Filter::Bilinear => {
+                let mut pixels = Vec::with_capacity(width * height);
+                for y in 0..height {
+                    let v = (y as f32 + 0.5) * sy - 0.5;
+                    for x in 0..width {
+                        pixels.push(bilinear(self, (x as f32 + 0.5) * sx - 0.5, v));
+                    }
+                }
+                pixels
+            }
+            Filter::Box => self.box_filter(width, height),
+        };
+        Canvas::new(width, height, &pixels)
+    }
+
+    /// Scale to fit inside `width` by `height` keeping the aspect ratio,
+    /// centered with `background` filling the bars left over.
+    pub fn fit(&self, width: usize, height: usize, filter: Filter, background: u32) -> Canvas {
+        if self.width == 0 || self.height == 0 {
+            return Canvas::new(width, height, &vec![background; width * height]);
+        }
+        let scale = f32::min(
+            width as f32 / self.width as f32,
+            height as f32 / self.height as f32,
+        );
+        let w = usize::min((self.width as f32 * scale).round() as usize, width);
+        let h = usize::min((self.height as f32 * scale).round() as usize, height);
+        let scaled = self.resize(w, h, filter);
+        let mut canvas = Canvas::new(width, height, &vec![background; width * height]);
+        let (left, top) = ((width - w) / 2, (height - h) / 2);
+        for y in 0..h {
+            let start = (top + y) * width + left;
+            canvas.pixels[start..start + w].copy_from_slice(&scaled.pixels[y * w..(y + 1) * w]);
+        }
+        canvas
+    }
+
+    /// Scale to cover all of `width` by `height` keeping the aspect ratio,
+    /// cropping whatever hangs over the sides evenly.
+    pub fn fill(&self, width: usize, height: usize, filter: Filter) -> Canvas {
+        if self.width == 0 || self.height == 0 {
+            return self.resize(width, height, filter);
+        }
+        let scale = f32::max(
+            width as f32 / self.width as f32,
+            height as f32 / self.height as f32,
+        );
+        let w = usize::max((self.width as f32 * scale).round() as usize, width);
+        let h = usize::max((self.height as f32 * scale).round() as usize, height);
+        let scaled = self.resize(w, h, filter);
+        let (left, top) = ((w - width) / 2, (h - height) / 2);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in top..top + height {
+            pixels.extend_from_slice(&scaled.pixels[y * w + left..y * w + left + width]);
+        }
+        Canvas::new(width, height, &pixels)
+    }
+
+    /// Scale to exactly `width` by `height`, squashing or stretching as needed.
+    pub fn stretch(&self, width: usize, height: usize, filter: Filter) -> Canvas {
+        self.resize(width, height, filter)
+    }
+
+    // average the area each new pixel covers, a row at a time then a column
+    // at a time, with colors weighted by alpha
+    fn box_filter(&self, width: usize, height: usize) -> Vec<u32> {
+        let premultiplied: Vec<[f32; 4]> = self
+            .pixels
+            .iter()
+            .map(|p| {
+                let a = alpha(*p) as f32 / 255.0;
+                [
+                    a,
+                    ((p >> 16) & 0xff) as f32 * a,
+                    ((p >> 8) & 0xff) as f32 * a,
+                    (p & 0xff) as f32 * a,
+                ]
+            })
+            .collect();
+        let columns = spans(self.width, width);
+        let mut rows = vec![[0.0; 4]; width * self.height];
+        for y in 0..self.height {
+            for (x, span) in columns.iter().enumerate() {
+                rows[y * width + x] = average(span, |i| premultiplied[y * self.width + i]);
+            }
+        }
+        let mut pixels = Vec::with_capacity(width * height);
+        for span in spans(self.height, height) {
+            for x in 0..width {
+                let [a, r, g, b] = average(&span, |i| rows[i * width + x]);
+                if a <= 0.0 {
+                    pixels.push(0);
+                    continue;
+                }
+                let channel = |v: f32| (v / a).round().min(255.0) as u32;
+                pixels.push(
+                    ((a * 255.0).round() as u32) << 24
+                        | channel(r) << 16
+                        | channel(g) << 8
+                        | channel(b),
+                );
+            }
+        }
+        pixels
+    }
+}
+
+// for each of `to` cells spread over `from`, the cells of `from` it covers
+// and how much of each
+fn spans(from: usize, to: usize) -> Vec<Vec<(usize, f32)>> {
+    let scale = from as f32 / to as f32;
+    (0..to)
+        .map(|i| {
+            let start = i as f32 * scale;
+            let end = start + scale;
+            let last = usize::min(end.ceil() as usize, from);
+            (start as usize..last)
+                .map(|j| {
+                    let covered = f32::min(end, j as f32 + 1.0) - f32::max(start, j as f32);
+                    (j, covered / scale)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn average(span: &[(usize, f32)], value: impl Fn(usize) -> [f32; 4]) -> [f32; 4] {
+    let mut sum = [0.0; 4];
+    for (i, weight) in span {
+        let v = value(*i);
+        for c in 0..4 {
+            sum[c] += v[c] * weight;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blend::BlendMode;
+    use crate::golden::assert_golden;
+
+    const RED: u32 = 0xffff_0000;
+    const BLUE: u32 = 0xff00_00ff;
+
+    #[test]
+    fn nearest_and_bilinear() {
+        let canvas = Canvas::new(2, 1, &[RED, BLUE]);
+        assert_eq!(
+            canvas.resize(4, 1, Filter::Nearest).pixels,
+            vec![RED, RED, BLUE, BLUE]
+        );
+        assert_eq!(
+            canvas.resize(1, 2, Filter::Nearest).pixels,
+            vec![BLUE, BLUE]
+        );
+        assert_eq!(
+            canvas.resize(4, 1, Filter::Bilinear).pixels,
+            vec![RED, 0xffbf_0040, 0xff40_00bf, BLUE]
+        );
+    }
+
+    #[test]
+    fn box_averages_what_it_covers() {
+        let canvas = Canvas::new(4, 1, &[0xff00_0000, 0xffff_ffff, 0xffff_ffff, 0]);
+        // the transparent pixel takes from the alpha but not the color
+        assert_eq!(
+            canvas.resize(2, 1, Filter::Box).pixels,
+            vec![0xff80_8080, 0x80ff_ffff]
+        );
+        // the middle pixel covers a third of each, so is half and half
+        let canvas = Canvas::new(2, 1, &[0xff00_0000, 0xffff_ffff]);
+        assert_eq!(
+            canvas.resize(3, 1, Filter::Box).pixels,
+            vec![0xff00_0000, 0xff80_8080, 0xffff_ffff]
+        );
+    }
+
+    #[test]
+    fn fit_letterboxes_and_fill_crops() {
+        let canvas = Canvas::new(2, 1, &[RED, BLUE]);
+        let fitted = canvas.fit(2, 3, Filter::Nearest, 0xff00_0000);
+        assert_eq!(
+            fitted.pixels,
+            vec![
+                0xff00_0000,
+                0xff00_0000,
+                RED,
+                BLUE,
+                0xff00_0000,
+                0xff00_0000
+            ]
+        );
+        let filled = canvas.fill(2, 2, Filter::Nearest);
+        // scaled to 4x2 and the middle two columns kept
+        assert_eq!(filled.pixels, vec![RED, BLUE, RED, BLUE]);
+        assert_eq!(
+            canvas.stretch(2, 2, Filter::Nearest).pixels,
+            vec![RED, BLUE, RED, BLUE]
+        );
+    }
+
+    #[test]
+    fn resizing_matches_golden() {
+        // a soft diagonal ramp with hard stripes
+        let pixels: Vec<u32> = (0..256)
+            .map(|i| {
+                let (x, y) = (i % 16, i / 16);
+                let stripe = if x % 4 == 0 { 0xff } else { 0x40 };
+                0xff00_0000 | ((x + y) * 8) << 16 | stripe << 8 | (y * 16)
+            })
+            .collect();
+        let image = Canvas::new(16, 16, &pixels);
+        let mut canvas = Canvas::new(64, 48, &[0xff00_0000; 64 * 48]);
+        let filters = [Filter::Nearest, Filter::Bilinear, Filter::Box];
+        for (i, filter) in filters.iter().enumerate() {
+            let x = i as isize * 21;
+            let big = image.resize(20, 20, *filter);
+            canvas
+                .draw_canvas(&big, x, 0, BlendMode::Replace, 1.0)
+                .unwrap();
+            let small = image.resize(7, 7, *filter);
+            canvas
+                .draw_canvas(&small, x, 22, BlendMode::Replace, 1.0)
+                .unwrap();
+            let fitted = image.fit(12, 8, *filter, 0xff80_8080);
+            canvas
+                .draw_canvas(&fitted, x + 8, 22, BlendMode::Replace, 1.0)
+                .unwrap();
+            let filled = image.fill(12, 8, *filter);
+            canvas
+                .draw_canvas(&filled, x + 8, 32, BlendMode::Replace, 1.0)
+                .unwrap();
+        }
+        assert_golden("resizing", &canvas);
+    }
+}
@@ -0,0 +1,512 @@
+use crate::canvas::Canvas;
+use std::f32::consts::PI;
+
+/// Which parts of a self intersecting polygon count as inside.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FillRule {
+    /// Inside where a ray out of the shape crosses an odd number of edges.
+    EvenOdd,
+    /// Inside where the edges wind around the point at all.
+    NonZero,
+}
+
+// walk the first quadrant of an ellipse centered on the origin with the
+// midpoint algorithm, from the top (0, ry) round to the side (rx, 0), in
+// i128 as the decision variables grow with the fourth power of the radii
+fn quadrant(rx: isize, ry: isize, mut f: impl FnMut(isize, isize)) {
+    let (rx, ry) = (rx as i128, ry as i128);
+    let (rx2, ry2) = (rx * rx, ry * ry);
+    let (mut x, mut y) = (0, ry);
+    let (mut px, mut py) = (0, 2 * rx2 * y);
+    // decision variables are kept four times larger to stay in integers
+    let mut p = 4 * ry2 - 4 * rx2 * ry + rx2;
+    while px < py {
+        f(x as isize, y as isize);
+        x += 1;
+        px += 2 * ry2;
+        if p < 0 {
+            p += 4 * (ry2 + px);
+        } else {
+            y -= 1;
+            py -= 2 * rx2;
+            p += 4 * (ry2 + px - py);
+        }
+    }
+    p = ry2 * (2 * x + 1) * (2 * x + 1) + 4 * rx2 * (y - 1) * (y - 1) - 4 * rx2 * ry2;
+    while y >= 0 {
+        f(x as isize, y as isize);
+        y -= 1;
+        py -= 2 * rx2;
+        if p > 0 {
+            p += 4 * (rx2 - py);
+        } else {
+            x += 1;
+            px += 2 * ry2;
+            p += 4 * (rx2 - py + px);
+        }
+    }
+}
+
+// the last of `len` positions starting at `start`, saturating rather than
+// overflowing for huge lengths
+fn last_of(start: isize, len: usize) -> isize {
+    start
+        .saturating_add(len.min(isize::MAX as usize) as isize)
+        .saturating_sub(1)
+}
+
+// whether the direction from a center to (dx, dy) lies within an arc
+fn in_arc(dx: isize, dy: isize, start: f32, end: f32) -> bool {
+    let sweep = end - start;
+    if sweep >= 2.0 * PI {
+        return true;
+    }
+    let angle = (dy as f32).atan2(dx as f32);
+    (angle - start).rem_euclid(2.0 * PI) <= sweep
+}
+
+impl Canvas {
+    // fill pixels x0 through x1 inclusive of row y, clipped to the canvas
+    pub(super) fn fill_span(&mut self, y: isize, x0: isize, x1: isize, color: u32) {
+        if y < 0 || y >= self.height as isize {
+            return;
+        }
+        let start = x0.max(0);
+        let end = x1.min(self.width as isize - 1);
+        if start > end {
+            return;
+        }
+        let row = y as usize * self.width;
+        for p in &mut self.pixels[row + start as usize..=row + end as usize] {
+            *p = color;
+        }
+        self.damage(start, y, (end - start + 1) as usize, 1);
+    }
+
+    pub fn draw_rect(&mut self, x: isize, y: isize, width: usize, height: usize, color: u32) {
+        self.draw_rounded_rect(x, y, width, height, 0, color);
+    }
+
+    pub fn fill_rect(&mut self, x: isize, y: isize, width: usize, height: usize, color: u32) {
+        if width == 0 {
+            return;
+        }
+        let first = y.max(0);
+        let last = last_of(y, height).min(self.height as isize - 1);
+        for row in first..=last {
+            self.fill_span(row, x, last_of(x, width), color);
+        }
+    }
+
+    /// A rectangle whose corners are quarter circles of `radius`, shrunk to
+    /// fit if the rectangle is too small for it.
+    pub fn draw_rounded_rect(
+        &mut self,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+        radius: usize,
+        color: u32,
+    ) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let r = radius.min((width - 1) / 2).min((height - 1) / 2) as isize;
+        let (left, top) = (x, y);
+        let (right, bottom) = (x + width as isize - 1, y + height as isize - 1);
+        for cx in left + r..=right - r {
+            self.plot(cx, top, color);
+            self.plot(cx, bottom, color);
+        }
+        for cy in top + r..=bottom - r {
+            self.plot(left, cy, color);
+            self.plot(right, cy, color);
+        }
+        quadrant(r, r, |dx, dy| {
+            self.plot(right - r + dx, top + r - dy, color);
+            self.plot(left + r - dx, top + r - dy, color);
+            self.plot(right - r + dx, bottom - r + dy, color);
+            self.plot(left + r - dx, bottom - r + dy, color);
+        });
+    }
+
+    pub fn fill_rounded_rect(
+        &mut self,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+        radius: usize,
+        color: u32,
+    ) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let r = radius.min((width - 1) / 2).min((height - 1) / 2) as isize;
+        let (left, top) = (x, y);
+        let (right, bottom) = (x + width as isize - 1, y + height as isize - 1);
+        for row in top + r..=bottom - r {
+            self.fill_span(row, left, right, color);
+        }
+        quadrant(r, r, |dx, dy| {
+            self.fill_span(top + r - dy, left + r - dx, right - r + dx, color);
+            self.fill_span(bottom - r + dy, left + r - dx, right - r + dx, color);
+        });
+    }
+
+    pub fn draw_circle(&mut self, cx: isize, cy: isize, radius: usize, color: u32) {
+        self.draw_ellipse(cx, cy, radius, radius, color);
+    }
+
+    pub fn fill_circle(&mut self, cx: isize, cy: isize, radius: usize, color: u32) {
+        self.fill_ellipse(cx, cy, radius, radius, color);
+    }
+
+    /// An ellipse centered on `cx`, `cy` reaching `rx` pixels left and right
+    /// and `ry` up and down.
+    pub fn draw_ellipse(&mut self, cx: isize, cy: isize, rx: usize, ry: usize, color: u32) {
+        let (rx, ry) = (rx as isize, ry as isize);
+        if ry == 0 {
+            self.fill_span(cy, cx - rx, cx + rx, color);
+            return;
+        }
+        quadrant(rx, ry, |dx, dy| {
+            self.plot(cx + dx, cy + dy, color);
+            self.plot(cx - dx, cy + dy, color);
+            self.plot(cx + dx, cy - dy, color);
+            self.plot(cx - dx, cy - dy, color);
+        });
+    }
+
+    pub fn fill_ellipse(&mut self, cx: isize, cy: isize, rx: usize, ry: usize, color: u32) {
+        let (rx, ry) = (rx as isize, ry as isize);
+        if ry == 0 {
+            self.fill_span(cy, cx - rx, cx + rx, color);
+            return;
+        }
+        quadrant(rx, ry, |dx, dy| {
+            self.fill_span(cy + dy, cx - dx, cx + dx, color);
+            self.fill_span(cy - dy, cx - dx, cx + dx, color);
+        });
+    }
+
+    /// Part of a circle's outline, from `start` to `end` radians measured
+    /// clockwise (as y grows downwards) from the positive x axis.
+    pub fn draw_arc(
+        &mut self,
+        cx: isize,
+        cy: isize,
+        radius: usize,
+        start: f32,
+        end: f32,
+        color: u32,
+    ) {
+        let r = radius as isize;
+        quadrant(r, r, |dx, dy| {
+            for &(px, py) in [(dx, dy), (-dx, dy), (dx, -dy), (-dx, -dy)].iter() {
+                if in_arc(px, py, start, end) {
+                    self.plot(cx + px, cy + py, color);
+                }
+            }
+        });
+    }
+
+    /// The pie slice between the center and the arc `draw_arc` would draw.
+    pub fn fill_arc(
+        &mut self,
+        cx: isize,
+        cy: isize,
+        radius: usize,
+        start: f32,
+        end: f32,
+        color: u32,
+    ) {
+        let r = radius.min(isize::MAX as usize / 2) as isize;
+        let (width, height) = (self.width as isize, self.height as isize);
+        // only the rows and columns on the canvas are worked out
+        let top = (-r).max(-cy);
+        let bottom = r.min(height - 1 - cy);
+        if top > bottom || cx.saturating_add(r) < 0 || cx.saturating_sub(r) >= width {
+            return;
+        }
+        let near = if top <= 0 && bottom >= 0 {
+            0
+        } else {
+            top.abs().min(bottom.abs())
+        };
+        let far = top.abs().max(bottom.abs());
+        // a circle around the whole canvas covers every visible row edge to
+        // edge, otherwise walk the outline for the rows shown
+        let corners = [
+            (0, 0),
+            (width - 1, 0),
+            (0, height - 1),
+            (width - 1, height - 1),
+        ];
+        let covered = r > 2
+            && corners.iter().all(|&(x, y)| {
+                let (dx, dy) = ((x - cx) as f64, (y - cy) as f64);
+                dx * dx + dy * dy <= ((r - 2) as f64).powi(2)
+            });
+        let mut half_widths = vec![if covered { r } else { 0 }; (far - near + 1) as usize];
+        if !covered {
+            quadrant(r, r, |dx, dy| {
+                if (near..=far).contains(&dy) {
+                    let w = &mut half_widths[(dy - near) as usize];
+                    *w = (*w).max(dx);
+                }
+            });
+        }
+        for dy in top..=bottom {
+            let w = half_widths[(dy.abs() - near) as usize];
+            for dx in (-w).max(-cx)..=w.min(width - 1 - cx) {
+                if in_arc(dx, dy, start, end) {
+                    self.plot(cx + dx, cy + dy, color);
+                }
+            }
+        }
+    }
+
+    pub fn draw_triangle(
+        &mut self,
+        a: (isize, isize),
+        b: (isize, isize),
+        c: (isize, isize),
+        color: u32,
+    ) {
+        self.draw_polygon(&[a, b, c], color);
+    }
+
+    pub fn fill_triangle(
+        &mut self,
+        a: (isize, isize),
+        b: (isize, isize),
+        c: (isize, isize),
+        color: u32,
+    ) {
+        self.fill_polygon(&[a, b, c], FillRule::NonZero, color);
+    }
+
+    /// The closed outline through `points`.
+    pub fn draw_polygon(&mut self, points: &[(isize, isize)], color: u32) {
+        for (i, p) in points.iter().enumerate() {
+            self.segment(*p, points[(i + 1) % points.len()], color);
+        }
+    }
+
+    /// Fill the polygon through `points`, closing it back to the first point.
+    /// A pixel is inside when its center is.
+    pub fn fill_polygon(&mut self, points: &[(isize, isize)], rule: FillRule, color: u32) {
+        let points: Vec<(f32, f32)> = points.iter().map(|&(x, y)| (x as f32, y as f32)).collect();
+        self.fill_path(&points, rule, color);
+    }
+
+    // fill a polygon with fractional corners, where pixel x, y covers the
+    // square from (x, y) to (x + 1, y + 1)
+    pub(super) fn fill_path(&mut self, points: &[(f32, f32)], rule: FillRule, color: u32) {
+        if points.len() < 3 || points.iter().any(|p| !p.0.is_finite() || !p.1.is_finite()) {
+            return;
+        }
+        let min_y = points.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+        let max_y = points.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+        let first = (min_y.floor() as isize).max(0);
+        let last = (max_y.ceil() as isize).min(self.height as isize - 1);
+        let mut crossings: Vec<(f32, i32)> = vec![];
+        for y in first..=last {
+            let sample = y as f32 + 0.5;
+            crossings.clear();
+            for (i, &(x0, y0)) in points.iter().enumerate() {
+                let (x1, y1) = points[(i + 1) % points.len()];
+                if y0 == y1 || sample < y0.min(y1) || sample >= y0.max(y1) {
+                    continue;
+                }
+                let t = (sample - y0) / (y1 - y0);
+                crossings.push((x0 + t * (x1 - x0), if y1 > y0 { 1 } else { -1 }));
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                let inside = match rule {
+                    FillRule::EvenOdd => winding % 2 != 0,
+                    FillRule::NonZero => winding != 0,
+                };
+                if inside {
+                    // pixels whose centers lie between the two crossings
+                    let start = (pair[0].0 - 0.5).ceil() as isize;
+                    let end = (pair[1].0 - 0.5).ceil() as isize - 1;
+                    self.fill_span(y, start, end, color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::assert_golden;
+
+    const W: u32 = 0xffff_ffff;
+
+    fn rows(canvas: &Canvas) -> Vec<String> {
+        canvas
+            .pixels
+            .chunks(canvas.width)
+            .map(|row| {
+                row.iter()
+                    .map(|p| if *p == 0 { '.' } else { '#' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn blank(width: usize, height: usize) -> Canvas {
+        Canvas::new(width, height, &vec![0; width * height])
+    }
+
+    #[test]
+    fn rects_are_clipped() {
+        let mut canvas = blank(5, 4);
+        canvas.draw_rect(-1, 1, 4, 3, W);
+        canvas.fill_rect(3, -2, 10, 3, W);
+        assert_eq!(rows(&canvas), vec!["...##", "###..", "..#..", "###.."]);
+    }
+
+    #[test]
+    fn huge_shapes_are_clipped() {
+        let mut canvas = blank(3, 3);
+        canvas.fill_rect(-5, 1, usize::MAX, usize::MAX, W);
+        assert_eq!(rows(&canvas), vec!["...", "###", "###"]);
+
+        let mut canvas = blank(4, 4);
+        canvas.fill_arc(1, 1, usize::MAX, 0.0, PI / 2.0, W);
+        assert_eq!(rows(&canvas), vec!["....", ".###", ".###", ".###"]);
+        let mut canvas = blank(4, 4);
+        canvas.fill_arc(1_000_000, 1, 1_000_000, PI / 2.0, PI, W);
+        assert_eq!(rows(&canvas), vec!["....", "####", "####", "####"]);
+
+        let mut canvas = blank(3, 3);
+        canvas.fill_path(
+            &[(0.0, 0.0), (f32::NAN, 2.0), (3.0, 3.0)],
+            FillRule::NonZero,
+            W,
+        );
+        assert_eq!(rows(&canvas), vec!["...", "...", "..."]);
+    }
+
+    #[test]
+    fn rounded_rect_cuts_corners() {
+        let mut canvas = blank(7, 5);
+        canvas.fill_rounded_rect(0, 0, 7, 5, 2, W);
+        assert_eq!(
+            rows(&canvas),
+            vec![".#####.", "#######", "#######", "#######", ".#####."]
+        );
+        let mut canvas = blank(7, 5);
+        canvas.draw_rounded_rect(0, 0, 7, 5, 2, W);
+        assert_eq!(
+            rows(&canvas),
+            vec![".#####.", "#.....#", "#.....#", "#.....#", ".#####."]
+        );
+    }
+
+    #[test]
+    fn circle_outline_matches_fill() {
+        let mut outline = blank(9, 9);
+        outline.draw_circle(4, 4, 3, W);
+        let mut filled = blank(9, 9);
+        filled.fill_circle(4, 4, 3, W);
+        assert_eq!(
+            rows(&filled),
+            vec![
+                ".........",
+                "...###...",
+                "..#####..",
+                ".#######.",
+                ".#######.",
+                ".#######.",
+                "..#####..",
+                "...###...",
+                ".........",
+            ]
+        );
+        // every outline pixel is filled, and the outline is a closed ring
+        for (o, f) in outline.pixels.iter().zip(&filled.pixels) {
+            assert!(*o == 0 || *f != 0);
+        }
+        assert_eq!(rows(&outline)[4], ".#.....#.");
+        assert_eq!(rows(&outline)[1], "...###...");
+    }
+
+    #[test]
+    fn ellipse_and_arc() {
+        let mut canvas = blank(9, 3);
+        canvas.fill_ellipse(4, 1, 4, 1, W);
+        assert_eq!(rows(&canvas), vec![".#######.", "#########", ".#######."]);
+
+        // the lower right quarter of a circle, x then y growing
+        let mut canvas = blank(7, 7);
+        canvas.fill_arc(3, 3, 3, 0.0, PI / 2.0, W);
+        assert_eq!(
+            rows(&canvas),
+            vec![".......", ".......", ".......", "...####", "...####", "...###.", "...##..",]
+        );
+        let mut canvas = blank(7, 7);
+        canvas.draw_arc(3, 3, 3, 0.0, PI / 2.0, W);
+        assert_eq!(rows(&canvas)[3], "......#");
+        assert_eq!(rows(&canvas)[6], "...##..");
+        assert!(rows(&canvas)[..3].iter().all(|r| r == "......."));
+    }
+
+    #[test]
+    fn fill_rules_differ_on_overlaps() {
+        // the same square traced twice in the same direction
+        let square = [(1, 1), (5, 1), (5, 5), (1, 5)];
+        let twice: Vec<(isize, isize)> = square.iter().chain(square.iter()).cloned().collect();
+        let mut nonzero = blank(6, 6);
+        nonzero.fill_polygon(&twice, FillRule::NonZero, W);
+        let mut evenodd = blank(6, 6);
+        evenodd.fill_polygon(&twice, FillRule::EvenOdd, W);
+        assert_eq!(rows(&nonzero)[3], ".####.");
+        assert_eq!(rows(&evenodd)[3], "......");
+
+        let mut canvas = blank(6, 6);
+        canvas.fill_polygon(&square, FillRule::EvenOdd, W);
+        assert_eq!(
+            rows(&canvas),
+            vec!["......", ".####.", ".####.", ".####.", ".####.", "......"]
+        );
+    }
+
+    #[test]
+    fn triangles_clip() {
+        let mut canvas = blank(4, 4);
+        canvas.fill_triangle((-4, 0), (4, 0), (-4, 8), W);
+        assert_eq!(rows(&canvas), vec!["###.", "##..", "#...", "...."]);
+        let mut canvas = blank(4, 4);
+        canvas.draw_triangle((0, 0), (3, 0), (0, 3), W);
+        assert_eq!(rows(&canvas), vec!["####", "#.#.", "##..", "#..."]);
+    }
+
+    #[test]
+    fn shapes_match_golden() {
+        let (red, green, blue) = (0xffe0_4040, 0xff40_c040, 0xff40_60e0);
+        let mut canvas = Canvas::new(64, 48, &[0xff20_2020; 64 * 48]);
+        canvas.draw_rect(1, 1, 12, 9, red);
+        canvas.fill_rect(15, 1, 12, 9, green);
+        canvas.draw_rounded_rect(29, 1, 16, 10, 4, blue);
+        canvas.fill_rounded_rect(47, 1, 16, 10, 4, W);
+        canvas.draw_circle(7, 19, 6, W);
+        canvas.fill_circle(21, 19, 6, red);
+        canvas.draw_ellipse(37, 19, 9, 5, green);
+        canvas.fill_ellipse(55, 19, 7, 5, blue);
+        canvas.draw_arc(7, 36, 6, 0.0, std::f32::consts::PI, green);
+        canvas.fill_arc(21, 36, 6, 0.5, 4.0, W);
+        canvas.draw_triangle((30, 46), (36, 29), (42, 46), red);
+        let star = [(53, 28), (58, 46), (45, 34), (62, 34), (48, 46)];
+        canvas.fill_polygon(&star, FillRule::EvenOdd, blue);
+        assert_golden("shapes", &canvas);
+    }
+}
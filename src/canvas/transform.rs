@@ -0,0 +1,335 @@
+use crate::blend::{alpha, fade, BlendMode};
+use crate::canvas::Canvas;
+
+/// An affine map from one canvas's coordinates onto another's, taking x, y to
+/// `a * x + c * y + e`, `b * x + d * y + f`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform::scale(1.0, 1.0)
+    }
+
+    pub fn translate(x: f32, y: f32) -> Self {
+        Transform {
+            e: x,
+            f: y,
+            ..Transform::identity()
+        }
+    }
+
+    pub fn scale(x: f32, y: f32) -> Self {
+        Transform {
+            a: x,
+            b: 0.0,
+            c: 0.0,
+            d: y,
+            e: 0.0,
+            f: 0.0,
+        }
+    }
+
+    /// Turn by `angle` radians clockwise about the origin.
+    pub fn rotate(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Transform {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            e: 0.0,
+            f: 0.0,
+        }
+    }
+
+    /// Turn by `angle` radians clockwise about `x`, `y`.
+    pub fn rotate_about(angle: f32, x: f32, y: f32) -> Self {
+        Transform::translate(-x, -y)
+            .then(Transform::rotate(angle))
+            .then(Transform::translate(x, y))
+    }
+
+    /// Slide x by `x` times y, and y by `y` times x.
+    pub fn shear(x: f32, y: f32) -> Self {
+        Transform {
+            a: 1.0,
+            b: y,
+            c: x,
+            d: 1.0,
+            e: 0.0,
+            f: 0.0,
+        }
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(self, next: Transform) -> Self {
+        Transform {
+            a: next.a * self.a + next.c * self.b,
+            b: next.b * self.a + next.d * self.b,
+            c: next.a * self.c + next.c * self.d,
+            d: next.b * self.c + next.d * self.d,
+            e: next.a * self.e + next.c * self.f + next.e,
+            f: next.b * self.e + next.d * self.f + next.f,
+        }
+    }
+
+    /// The transform undoing this one, if it doesn't flatten everything onto
+    /// a line.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.a * self.d - self.b * self.c;
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+        Some(Transform {
+            a,
+            b,
+            c,
+            d,
+            e: -(a * self.e + c * self.f),
+            f: -(b * self.e + d * self.f),
+        })
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+}
+
+/// How a transformed canvas picks colors between its pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Sampling {
+    // the pixel underneath, keeping hard edges
+    Nearest,
+    // a mix of the four pixels around, smoother when scaled or turned
+    Bilinear,
+}
+
+impl Canvas {
+    /// Draw `canvas` onto this one moved, scaled, turned or sheared by
+    /// `transform`, combining pixels like `draw_canvas`.
+    pub fn draw_canvas_transformed(
+        &mut self,
+        canvas: &Canvas,
+        transform: &Transform,
+        sampling: Sampling,
+        mode: BlendMode,
+        opacity: f32,
+    ) -> Result<(), &'static str> {
+        let inverse = transform
+            .inverse()
+            .ok_or("cannot draw canvas with a transform that has no inverse")?;
+        if canvas.width == 0 || canvas.height == 0 {
+            return Ok(());
+        }
+
+        // only visit the pixels the transformed canvas can land on
+        let (w, h) = (canvas.width as f32, canvas.height as f32);
+        let corners = [(0.0, 0.0), (w, 0.0), (0.0, h), (w, h)].map(|(x, y)| transform.apply(x, y));
+        let min = |f: fn(&(f32, f32)) -> f32| corners.iter().map(f).fold(f32::MAX, f32::min);
+        let max = |f: fn(&(f32, f32)) -> f32| corners.iter().map(f).fold(f32::MIN, f32::max);
+        let left = min(|p| p.0).floor().max(0.0) as isize;
+        let top = min(|p| p.1).floor().max(0.0) as isize;
+        let right = isize::min(max(|p| p.0).ceil() as isize, self.width as isize);
+        let bottom = isize::min(max(|p| p.1).ceil() as isize, self.height as isize);
+        if left >= right || top >= bottom {
+            return Ok(());
+        }
+        self.damage(left, top, (right - left) as usize, (bottom - top) as usize);
+
+        for y in top..bottom {
+            for x in left..right {
+                // where the middle of this pixel comes from
+                let (u, v) = inverse.apply(x as f32 + 0.5, y as f32 + 0.5);
+                if u < 0.0 || v < 0.0 || u >= w || v >= h {
+                    continue;
+                }
+                let src = match sampling {
+                    Sampling::Nearest => canvas.pixels[v as usize * canvas.width + u as usize],
+                    Sampling::Bilinear => bilinear(canvas, u - 0.5, v - 0.5),
+                };
+                let a = match mode {
+                    BlendMode::Replace => fade(255, opacity),
+                    _ => fade(alpha(src), opacity),
+                };
+                let index = y as usize * self.width + x as usize;
+                self.pixels[index] = mode.blend(self.pixels[index], src, a);
+            }
+        }
+        Ok(())
+    }
+}
+
+// mix the four pixels around x, y by how close each is, weighting colors by
+// their alpha so transparent pixels don't darken the edges of opaque ones
+pub(super) fn bilinear(canvas: &Canvas, x: f32, y: f32) -> u32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let pixel = |px: f32, py: f32| {
+        let px = (px.max(0.0) as usize).min(canvas.width - 1);
+        let py = (py.max(0.0) as usize).min(canvas.height - 1);
+        canvas.pixels[py * canvas.width + px]
+    };
+    let taps = [
+        (pixel(x0, y0), (1.0 - fx) * (1.0 - fy)),
+        (pixel(x0 + 1.0, y0), fx * (1.0 - fy)),
+        (pixel(x0, y0 + 1.0), (1.0 - fx) * fy),
+        (pixel(x0 + 1.0, y0 + 1.0), fx * fy),
+    ];
+    let mut sum = [0.0f32; 4];
+    for (p, weight) in taps.iter() {
+        let a = alpha(*p) as f32 * weight;
+        sum[0] += a;
+        for (i, shift) in [16, 8, 0].iter().enumerate() {
+            sum[i + 1] += ((p >> shift) & 0xff) as f32 * a;
+        }
+    }
+    if sum[0] <= 0.0 {
+        return 0;
+    }
+    let channel = |v: f32| (v / sum[0]).round().min(255.0) as u32;
+    (sum[0].round().min(255.0) as u32) << 24
+        | channel(sum[1]) << 16
+        | channel(sum[2]) << 8
+        | channel(sum[3])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::assert_golden;
+
+    const RED: u32 = 0xffff_0000;
+    const BLUE: u32 = 0xff00_00ff;
+
+    #[test]
+    fn composes_and_inverts() {
+        let t = Transform::scale(2.0, 3.0).then(Transform::translate(1.0, -1.0));
+        assert_eq!(t.apply(1.0, 1.0), (3.0, 2.0));
+        let (x, y) = t.inverse().unwrap().apply(3.0, 2.0);
+        assert!((x - 1.0).abs() < 1e-6 && (y - 1.0).abs() < 1e-6);
+        let (x, y) = Transform::rotate(std::f32::consts::FRAC_PI_2).apply(1.0, 0.0);
+        assert!(x.abs() < 1e-6 && (y - 1.0).abs() < 1e-6);
+        assert!(Transform::scale(0.0, 1.0).inverse().is_none());
+    }
+
+    #[test]
+    fn nearest_scales_turns_and_clips() {
+        let sprite = Canvas::new(2, 1, &[RED, BLUE]);
+        let mut canvas = Canvas::new(4, 2, &[0; 8]);
+        canvas
+            .draw_canvas_transformed(
+                &sprite,
+                &Transform::scale(2.0, 2.0),
+                Sampling::Nearest,
+                BlendMode::Normal,
+                1.0,
+            )
+            .unwrap();
+        assert_eq!(
+            canvas.pixels,
+            vec![RED, RED, BLUE, BLUE, RED, RED, BLUE, BLUE]
+        );
+
+        // a quarter turn stands it upright, swinging it off the left edge
+        let mut canvas = Canvas::new(2, 2, &[0; 4]);
+        let turn = Transform::rotate(std::f32::consts::FRAC_PI_2);
+        canvas
+            .draw_canvas_transformed(&sprite, &turn, Sampling::Nearest, BlendMode::Normal, 1.0)
+            .unwrap();
+        assert_eq!(canvas.pixels, vec![0, 0, 0, 0]);
+        let turn = turn.then(Transform::translate(1.0, 0.0));
+        canvas
+            .draw_canvas_transformed(&sprite, &turn, Sampling::Nearest, BlendMode::Normal, 1.0)
+            .unwrap();
+        assert_eq!(canvas.pixels, vec![RED, 0, BLUE, 0]);
+        assert!(canvas
+            .draw_canvas_transformed(
+                &sprite,
+                &Transform::scale(0.0, 0.0),
+                Sampling::Nearest,
+                BlendMode::Normal,
+                1.0
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn bilinear_blends_between_pixels() {
+        let sprite = Canvas::new(2, 1, &[0xff00_0000, 0xffff_ffff]);
+        let mut canvas = Canvas::new(4, 1, &[0; 4]);
+        canvas
+            .draw_canvas_transformed(
+                &sprite,
+                &Transform::scale(2.0, 1.0),
+                Sampling::Bilinear,
+                BlendMode::Replace,
+                1.0,
+            )
+            .unwrap();
+        // edges hold their color, the middle fades a quarter and three quarters across
+        assert_eq!(
+            canvas.pixels,
+            vec![0xff00_0000, 0xff40_4040, 0xffbf_bfbf, 0xffff_ffff]
+        );
+
+        // a transparent neighbour fades alpha without darkening the color
+        let sprite = Canvas::new(2, 1, &[0xffff_ffff, 0]);
+        assert_eq!(bilinear(&sprite, 0.5, 0.0), 0x80ff_ffff);
+    }
+
+    #[test]
+    fn transforms_match_golden() {
+        let checks: Vec<u32> = (0..64)
+            .map(|i| {
+                if (i % 8 / 2 + i / 16) % 2 == 0 {
+                    RED
+                } else {
+                    0xffff_ffff
+                }
+            })
+            .collect();
+        let checks = Canvas::new(8, 8, &checks);
+        let mut canvas = Canvas::new(64, 48, &[0xff20_2020; 64 * 48]);
+        let turn = Transform::scale(2.0, 2.0)
+            .then(Transform::rotate_about(0.5, 8.0, 8.0))
+            .then(Transform::translate(4.0, 4.0));
+        let samplings = [Sampling::Nearest, Sampling::Bilinear];
+        for (i, sampling) in samplings.iter().enumerate() {
+            let moved = turn.then(Transform::translate(i as f32 * 24.0, 0.0));
+            canvas
+                .draw_canvas_transformed(&checks, &moved, *sampling, BlendMode::Normal, 1.0)
+                .unwrap();
+        }
+        let sheared = Transform::shear(0.5, 0.0)
+            .then(Transform::scale(1.5, 1.5))
+            .then(Transform::translate(4.0, 32.0));
+        canvas
+            .draw_canvas_transformed(
+                &checks,
+                &sheared,
+                Sampling::Bilinear,
+                BlendMode::Normal,
+                0.7,
+            )
+            .unwrap();
+        assert_golden("transforms", &canvas);
+    }
+}
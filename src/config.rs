@@ -1,27 +1,46 @@
+use crate::calibration::Calibration;
 use crate::canvas::Canvas;
+use crate::display::Display;
+use crate::display::DisplayGuard;
 use crate::framebuffer::Framebuffer;
 use std::error::Error;
+use std::fs::File;
 use std::path::Path;
 use std::time::Instant;
 
 use crate::input::event_input::EventInput;
+use crate::input::recorded_input::RecordingInput;
+use crate::input::scripted_input::ScriptedInput;
+use crate::input::Input;
 use crate::input::InputEvent;
+use crate::input::Orientation;
 use crate::point::*;
+use crate::signal::SignalHandlers;
 use crate::streamed_data::*;
 use crate::swipe::*;
+use crate::touch::*;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// longest the run loop sleeps before checking for termination signals
+const SIGNAL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
 
 pub enum RunResponse {
     Exit,
     NothingChanged,
     Draw,
+    /// Push only the areas of the canvas drawn to since the last frame.
+    DrawDamaged,
 }
 
 #[derive(Debug)]
 pub struct Config {
-    framebuffer: Arc<Mutex<Framebuffer>>,
-    input_device: Arc<Mutex<EventInput>>,
+    display: Arc<Mutex<Box<dyn Display>>>,
+    input_device: Arc<Mutex<Box<dyn Input>>>,
+    calibration: Calibration,
 }
 
 #[derive(Debug)]
@@ -29,6 +48,7 @@ pub enum Event {
     Startup,
     Timer(usize, usize),
     Swipe(Swipe),
+    Touch(Touch),
 }
 
 impl Config {
@@ -49,31 +69,77 @@ impl Config {
             input_max_height,
         )?;
 
-        Ok(Config {
-            framebuffer: Arc::new(Mutex::new(framebuffer)),
-            input_device: Arc::new(Mutex::new(input_device)),
-        })
+        let mut config = Config::with_backends(framebuffer, input_device);
+        config.set_calibration(saved_calibration(&Calibration::default_path()));
+        Ok(config)
     }
 
     pub fn auto() -> Result<Self, Box<dyn Error>> {
         let framebuffer = Framebuffer::auto()?;
         let input_device = EventInput::auto()?;
-        Ok(Config {
-            input_device: Arc::new(Mutex::new(input_device)),
-            framebuffer: Arc::new(Mutex::new(framebuffer)),
-        })
+        let mut config = Config::with_backends(framebuffer, input_device);
+        config.set_calibration(saved_calibration(&Calibration::default_path()));
+        Ok(config)
+    }
+
+    /// Build a config from any display and input source, such as a
+    /// `HeadlessDisplay` and `ScriptedInput` when running without a device.
+    pub fn with_backends(
+        display: impl Display + 'static,
+        mut input_device: impl Input + 'static,
+    ) -> Self {
+        input_device.set_screen_size(display.width(), display.height());
+        Config {
+            display: Arc::new(Mutex::new(Box::new(display))),
+            input_device: Arc::new(Mutex::new(Box::new(input_device))),
+            calibration: Calibration::identity(),
+        }
+    }
+
+    /// Transform applied to every touch point, see `Calibrator` for measuring one.
+    /// `new` and `auto` load the one saved at `Calibration::default_path`,
+    /// using the identity if it can't be read.
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
+
+    /// Flip whole frames onto the screen rather than drawing on it, so a
+    /// `Framebuffer` from `new` or `auto` never tears. Takes effect when the
+    /// run starts, see `Framebuffer::set_double_buffering`.
+    pub fn set_double_buffering(&mut self, enabled: bool) {
+        let mut display = self.display.lock().unwrap();
+        display.set_double_buffering(enabled);
+    }
+
+    /// Describe how the touch panel is rotated relative to the screen.
+    pub fn set_input_orientation(&mut self, orientation: Orientation) {
+        let mut input_device = self.input_device.lock().unwrap();
+        input_device.set_orientation(orientation);
+    }
+
+    /// Write every input event to a trace at `path` as it arrives, for
+    /// playing back later with `ReplayInput`.
+    pub fn record_input<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Box<dyn Error>> {
+        // open the trace first so a failure leaves the input as it was
+        let trace = File::create(path)?;
+        let mut input_device = self.input_device.lock().unwrap();
+        let input = std::mem::replace(&mut *input_device, Box::new(ScriptedInput::default()));
+        *input_device = Box::new(RecordingInput::from_file(input, trace));
+        Ok(())
     }
 
     pub fn screen_width(&self) -> usize {
-        let fb = self.framebuffer.lock().unwrap();
-        return fb.width();
+        let fb = self.display.lock().unwrap();
+        fb.width()
     }
 
     pub fn screen_height(&self) -> usize {
-        let fb = self.framebuffer.lock().unwrap();
-        return fb.height();
+        let fb = self.display.lock().unwrap();
+        fb.height()
     }
 
+    /// Run `f` for every event until it asks to exit or fails. The display is
+    /// restored and the timer and input threads are stopped before returning.
     pub fn run(
         &mut self,
         mut f: impl FnMut(&mut Canvas, Event) -> Result<RunResponse, Box<dyn Error>> + 'static,
@@ -81,64 +147,63 @@ impl Config {
         let start = Instant::now();
         let mut last_t = 0 as usize;
 
-        let mut fb = self.framebuffer.lock().unwrap();
+        // shuts the display down however we leave, even by panicking
+        let mut fb = DisplayGuard::new(self.display.lock().unwrap());
 
         let w = fb.width();
         let h = fb.height();
-        let mut canvas = Canvas {
-            width: w,
-            height: h,
-            pixels: vec![0; w * h],
-        };
+        let mut canvas = Canvas::new(w, h, &vec![0; w * h]);
 
         if let Err(err) = fb.setup() {
             // try to shut down because because being stuck in graphics mode is really bad
             fb.shutdown()?;
-            eprintln!("Error occured in user run loop: {}", err);
-            std::process::exit(0);
+            return Err(err);
         }
 
-        match f(&mut canvas, Event::Startup) {
-            Ok(RunResponse::Draw) => {
-                fb.write_frame(&canvas.pixels);
-            }
-            Ok(RunResponse::Exit) => {
-                fb.shutdown()?;
-                std::process::exit(0);
-            }
-            Ok(RunResponse::NothingChanged) => {
-                //Question: should we show something if the first run doesn't say to paint?
-            }
-            Err(err) => {
-                fb.shutdown()?;
-                eprintln!("Error occured in user run loop: {}", err);
-                std::process::exit(0);
-            }
+        let signals = SignalHandlers::install();
+
+        //Question: should we show something if the first run doesn't say to paint?
+        let response = f(&mut canvas, Event::Startup);
+        if let Some(result) = handle_response(&mut fb, &mut canvas, response) {
+            return result;
         }
 
         let mut swipe_mem = StreamedSwipe {
             swipe: None,
             streamed_point: StreamedPoint::Nothing,
+            calibration: self.calibration,
         };
 
-        let (timer_tx, timer_rx) = flume::unbounded();
+        // stops and joins the threads below however we leave
+        let mut workers = Workers::new();
+
+        // the timer and input threads share one channel so the run loop can
+        // sleep until either has something for it
+        let (event_tx, event_rx) = flume::unbounded();
 
-        std::thread::spawn(move || loop {
-            let cur_time = start.elapsed().as_millis() as usize;
-            let delta_t = cur_time - last_t;
-            last_t = cur_time;
-            timer_tx
-                .send(Event::Timer(delta_t, cur_time))
-                .expect("something went wrong sending timer");
-            std::thread::sleep(std::time::Duration::from_millis(1000 / 60));
+        let timer_tx = event_tx.clone();
+        let running = workers.running.clone();
+        workers.spawn(move || {
+            while running.load(Ordering::SeqCst) {
+                let cur_time = start.elapsed().as_millis() as usize;
+                let delta_t = cur_time - last_t;
+                last_t = cur_time;
+                if timer_tx.send(Event::Timer(delta_t, cur_time)).is_err() {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(1000 / 60));
+            }
         });
 
-        let (swipe_tx, swipe_rx) = flume::unbounded();
+        let mut touch_mem = StreamedTouches::with_calibration(self.calibration);
+
+        let swipe_tx = event_tx;
 
         let id = self.input_device.clone();
-        std::thread::spawn(move || {
+        let running = workers.running.clone();
+        workers.spawn(move || {
             let mut i = id.lock().unwrap();
-            i.on_event(move |ev| {
+            let result = i.on_event(&running, &mut move |ev| {
                 let stream = match ev {
                     InputEvent::PartialX(x, time) => {
                         swipe_mem.update(SwipeFragment::PointFragment(PointFragment::X(time, x)))
@@ -152,56 +217,301 @@ impl Config {
                 match stream {
                     StreamedState::Complete(swipe) | StreamedState::Standalone(swipe) => {
                         swipe_tx
-                            .send(swipe)
-                            .expect("something went wrong when sending swipe");
+                            .send(Event::Swipe(swipe))
+                            .map_err(|_| "run loop stopped listening for swipes")?;
+                    }
+                    StreamedState::Incomplete => {}
+                }
+                let touches = match ev {
+                    InputEvent::MultiSlot(slot) => touch_mem.update(TouchFragment::Slot(slot)),
+                    InputEvent::MultiTrackingId(id, time) => {
+                        touch_mem.update(TouchFragment::TrackingId(time, id))
+                    }
+                    InputEvent::MultiX(x, time) => touch_mem.update(TouchFragment::X(time, x)),
+                    InputEvent::MultiY(y, time) => touch_mem.update(TouchFragment::Y(time, y)),
+                    InputEvent::Sync(time) => touch_mem.update(TouchFragment::Sync(time)),
+                    _ => StreamedState::Incomplete,
+                };
+                match touches {
+                    StreamedState::Complete(touches) | StreamedState::Standalone(touches) => {
+                        for touch in touches {
+                            swipe_tx
+                                .send(Event::Touch(touch))
+                                .map_err(|_| "run loop stopped listening for touches")?;
+                        }
                     }
                     StreamedState::Incomplete => {}
                 }
                 Ok(())
-            })
-            .expect("not sure why listening to event device would fail");
+            });
+            if let Err(err) = result {
+                if running.load(Ordering::SeqCst) {
+                    eprintln!("Error occured reading input: {}", err);
+                }
+            }
         });
 
         loop {
-            match timer_rx.try_recv() {
-                Ok(t) => match f(&mut canvas, t) {
-                    Ok(RunResponse::Draw) => {
-                        fb.write_frame(&canvas.pixels);
-                    }
-                    Ok(RunResponse::Exit) => {
-                        fb.shutdown()?;
-                        std::process::exit(0);
-                    }
-                    Ok(RunResponse::NothingChanged) => {}
-                    Err(err) => {
-                        fb.shutdown()?;
-                        eprintln!("Error occured in user run loop: {}", err);
-                        std::process::exit(0);
+            if let Some(signal) = signals.received() {
+                fb.shutdown()?;
+                return Err(format!("interrupted by signal {}", signal).into());
+            }
+
+            match event_rx.recv_timeout(SIGNAL_CHECK_INTERVAL) {
+                Ok(e) => {
+                    let response = f(&mut canvas, e);
+                    if let Some(result) = handle_response(&mut fb, &mut canvas, response) {
+                        return result;
                     }
-                },
-                Err(flume::TryRecvError::Empty) => (),
-                Err(flume::TryRecvError::Disconnected) => panic!("why would timer disconnect!"),
+                }
+                Err(flume::RecvTimeoutError::Timeout) => (),
+                Err(flume::RecvTimeoutError::Disconnected) => panic!("why would timer disconnect!"),
             };
+        }
+    }
+}
 
-            match swipe_rx.try_recv() {
-                Ok(s) => match f(&mut canvas, Event::Swipe(s.clone())) {
-                    Ok(RunResponse::Draw) => {
-                        fb.write_frame(&canvas.pixels);
+// The calibration saved at `path`, or the identity if there's none or it
+// can't be read, so a bad file doesn't stop the app from starting.
+fn saved_calibration(path: &Path) -> Calibration {
+    if !path.exists() {
+        return Calibration::identity();
+    }
+    Calibration::load(path).unwrap_or_else(|err| {
+        eprintln!(
+            "Error occured loading calibration from {}: {}",
+            path.display(),
+            err
+        );
+        Calibration::identity()
+    })
+}
+
+// Act on what the user callback asked for, returning what `run` should return
+// if it's time to stop.
+fn handle_response(
+    fb: &mut DisplayGuard,
+    canvas: &mut Canvas,
+    response: Result<RunResponse, Box<dyn Error>>,
+) -> Option<Result<(), Box<dyn Error>>> {
+    match response {
+        Ok(RunResponse::Draw) => {
+            fb.write_frame(&canvas.pixels);
+            canvas.take_damage();
+            None
+        }
+        Ok(RunResponse::DrawDamaged) => {
+            for rect in canvas.take_damage() {
+                fb.write_region(&canvas.pixels, rect);
+            }
+            fb.present();
+            None
+        }
+        Ok(RunResponse::NothingChanged) => None,
+        Ok(RunResponse::Exit) => Some(fb.shutdown()),
+        Err(err) => {
+            if let Err(shutdown_err) = fb.shutdown() {
+                eprintln!("Error occured shutting down display: {}", shutdown_err);
+            }
+            Some(Err(err))
+        }
+    }
+}
+
+// The threads feeding the run loop, stopped and joined when dropped.
+struct Workers {
+    running: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Workers {
+    fn new() -> Self {
+        Workers {
+            running: Arc::new(AtomicBool::new(true)),
+            handles: vec![],
+        }
+    }
+
+    fn spawn(&mut self, f: impl FnOnce() + Send + 'static) {
+        self.handles.push(std::thread::spawn(f));
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::headless_display::HeadlessDisplay;
+    use crate::input::recorded_input::ReplayInput;
+    use crate::rect::Rect;
+    use crate::util::color_from_rgb;
+
+    #[test]
+    fn unreadable_calibration_falls_back_to_identity() {
+        let path = std::env::temp_dir().join(format!(
+            "conifer-calibration-fallback-{}",
+            std::process::id()
+        ));
+        assert_eq!(saved_calibration(&path), Calibration::identity());
+        std::fs::write(&path, "1 0 0 not a number").unwrap();
+        assert_eq!(saved_calibration(&path), Calibration::identity());
+        let shifted = Calibration {
+            matrix: [1.0, 0.0, 5.0, 0.0, 1.0, 0.0],
+        };
+        shifted.save(&path).unwrap();
+        assert_eq!(saved_calibration(&path), shifted);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn headless_run_draws_scripted_swipe() {
+        let display = HeadlessDisplay::new(4, 4);
+        let input = ScriptedInput::swipe(&[(1, 1), (2, 3)]);
+        let mut config = Config::with_backends(display.clone(), input);
+        let white = color_from_rgb(255, 255, 255);
+        config
+            .run(move |canvas, event| {
+                if let Event::Swipe(s) = event {
+                    if s.finished {
+                        return Ok(RunResponse::Exit);
                     }
-                    Ok(RunResponse::Exit) => {
-                        fb.shutdown()?;
-                        std::process::exit(0);
+                    for p in s.points {
+                        canvas.set_pixel(p.x as usize, p.y as usize, white);
                     }
-                    Ok(RunResponse::NothingChanged) => {}
-                    Err(err) => {
-                        fb.shutdown()?;
-                        eprintln!("Error occured in user run loop: {}", err);
-                        std::process::exit(0);
+                    return Ok(RunResponse::Draw);
+                }
+                Ok(RunResponse::NothingChanged)
+            })
+            .unwrap();
+
+        let frame = display.last_frame().expect("swipe should have been drawn");
+        assert_eq!(frame.pixels[4 + 1], white);
+        assert_eq!(frame.pixels[3 * 4 + 2], white);
+        assert_eq!(display.setup_count(), 1);
+        assert_eq!(display.shutdown_count(), 1);
+    }
+
+    #[test]
+    fn recorded_input_replays_the_same_swipe() {
+        let draw = |config: &mut Config| {
+            let white = color_from_rgb(255, 255, 255);
+            config
+                .run(move |canvas, event| {
+                    if let Event::Swipe(s) = event {
+                        if s.finished {
+                            return Ok(RunResponse::Exit);
+                        }
+                        for p in s.points {
+                            canvas.set_pixel(p.x as usize, p.y as usize, white);
+                        }
+                        return Ok(RunResponse::Draw);
                     }
-                },
-                Err(flume::TryRecvError::Empty) => (),
-                Err(flume::TryRecvError::Disconnected) => panic!("why would events disconnect!"),
-            };
-        }
+                    Ok(RunResponse::NothingChanged)
+                })
+                .unwrap();
+        };
+        let path = std::env::temp_dir().join(format!("conifer-run-trace-{}", std::process::id()));
+
+        let recorded = HeadlessDisplay::new(4, 4);
+        let input = ScriptedInput::swipe(&[(1, 1), (2, 3), (3, 0)]);
+        let mut config = Config::with_backends(recorded.clone(), input);
+        config.record_input(&path).unwrap();
+        draw(&mut config);
+
+        let replayed = HeadlessDisplay::new(4, 4);
+        let input = ReplayInput::load(&path, true).unwrap();
+        draw(&mut Config::with_backends(replayed.clone(), input));
+        assert_eq!(
+            replayed.last_frame().unwrap().pixels,
+            recorded.last_frame().unwrap().pixels
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn damaged_draw_pushes_only_changes() {
+        let display = HeadlessDisplay::new(4, 4);
+        let input = ScriptedInput::swipe(&[(1, 1), (2, 3)]);
+        let mut config = Config::with_backends(display.clone(), input);
+        let white = color_from_rgb(255, 255, 255);
+        config
+            .run(move |canvas, event| match event {
+                Event::Swipe(s) if s.finished => Ok(RunResponse::Exit),
+                Event::Swipe(s) => {
+                    let p = s.last();
+                    canvas.set_pixel(p.x as usize, p.y as usize, white);
+                    Ok(RunResponse::DrawDamaged)
+                }
+                _ => Ok(RunResponse::NothingChanged),
+            })
+            .unwrap();
+
+        assert_eq!(
+            display.regions(),
+            vec![Rect::new(1, 1, 1, 1), Rect::new(2, 3, 1, 1)]
+        );
+        let frame = display.last_frame().unwrap();
+        assert_eq!(frame.pixels[4 + 1], white);
+        assert_eq!(frame.pixels[3 * 4 + 2], white);
+        assert_eq!(frame.pixels.iter().filter(|p| **p == white).count(), 2);
+    }
+
+    #[test]
+    fn callback_error_is_returned() {
+        let display = HeadlessDisplay::new(4, 4);
+        let mut config = Config::with_backends(display.clone(), ScriptedInput::default());
+        let result = config.run(|_, event| match event {
+            Event::Timer(_, _) => Err("out of lives".into()),
+            _ => Ok(RunResponse::NothingChanged),
+        });
+        assert_eq!(result.unwrap_err().to_string(), "out of lives");
+        assert_eq!(display.shutdown_count(), 1);
+
+        // the config can be run again afterwards
+        config.run(|_, _| Ok(RunResponse::Exit)).unwrap();
+        assert_eq!(display.setup_count(), 2);
+        assert_eq!(display.shutdown_count(), 2);
+    }
+
+    fn thread_cpu_time() -> Duration {
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut usage) };
+        let micros = |t: libc::timeval| t.tv_sec as u64 * 1_000_000 + t.tv_usec as u64;
+        Duration::from_micros(micros(usage.ru_utime) + micros(usage.ru_stime))
+    }
+
+    #[test]
+    fn idle_run_loop_sleeps() {
+        let mut config =
+            Config::with_backends(HeadlessDisplay::new(4, 4), ScriptedInput::default());
+        let before = thread_cpu_time();
+        config
+            .run(|_, event| match event {
+                Event::Timer(_, t) if t >= 500 => Ok(RunResponse::Exit),
+                _ => Ok(RunResponse::NothingChanged),
+            })
+            .unwrap();
+        // a spinning loop would use the whole half second
+        assert!(thread_cpu_time() - before < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn panicking_callback_shuts_display_down() {
+        let display = HeadlessDisplay::new(4, 4);
+        let mut config = Config::with_backends(display.clone(), ScriptedInput::default());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            config.run(|_, _| panic!("callback blew up"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(display.setup_count(), 1);
+        assert_eq!(display.shutdown_count(), 1);
     }
 }
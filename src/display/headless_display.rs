@@ -0,0 +1,107 @@
+use crate::canvas::Canvas;
+use crate::display::Display;
+use crate::rect::Rect;
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+#[derive(Debug, Default)]
+struct HeadlessState {
+    frames: Vec<Vec<u32>>,
+    // the area each frame updated, the whole screen for full frames
+    regions: Vec<Rect>,
+    setup_count: usize,
+    shutdown_count: usize,
+}
+
+/// An in-memory display that records every frame written to it.
+///
+/// Clones share the same recording, so a test can keep one handle and pass
+/// another to `Config::with_backends`.
+#[derive(Debug, Clone)]
+pub struct HeadlessDisplay {
+    width: usize,
+    height: usize,
+    state: Arc<Mutex<HeadlessState>>,
+}
+
+impl HeadlessDisplay {
+    pub fn new(width: usize, height: usize) -> Self {
+        HeadlessDisplay {
+            width,
+            height,
+            state: Arc::new(Mutex::new(HeadlessState::default())),
+        }
+    }
+
+    pub fn frames(&self) -> Vec<Vec<u32>> {
+        self.state.lock().unwrap().frames.clone()
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.state.lock().unwrap().frames.len()
+    }
+
+    pub fn last_frame(&self) -> Option<Canvas> {
+        let state = self.state.lock().unwrap();
+        state
+            .frames
+            .last()
+            .map(|pixels| Canvas::new(self.width, self.height, pixels))
+    }
+
+    /// The area of the screen each recorded frame updated.
+    pub fn regions(&self) -> Vec<Rect> {
+        self.state.lock().unwrap().regions.clone()
+    }
+
+    pub fn setup_count(&self) -> usize {
+        self.state.lock().unwrap().setup_count
+    }
+
+    pub fn shutdown_count(&self) -> usize {
+        self.state.lock().unwrap().shutdown_count
+    }
+}
+
+impl Display for HeadlessDisplay {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn setup(&mut self) -> Result<(), Box<dyn Error>> {
+        self.state.lock().unwrap().setup_count += 1;
+        Ok(())
+    }
+
+    fn shutdown(&mut self) -> Result<(), Box<dyn Error>> {
+        self.state.lock().unwrap().shutdown_count += 1;
+        Ok(())
+    }
+
+    fn write_frame(&mut self, pixels: &[u32]) {
+        let mut state = self.state.lock().unwrap();
+        state.frames.push(pixels.to_vec());
+        state.regions.push(Rect::new(0, 0, self.width, self.height));
+    }
+
+    fn write_region(&mut self, pixels: &[u32], rect: Rect) {
+        let mut state = self.state.lock().unwrap();
+        // the screen keeps whatever was outside the region
+        let mut frame = match state.frames.last() {
+            Some(last) => last.clone(),
+            None => vec![0; self.width * self.height],
+        };
+        for y in rect.y..rect.bottom() {
+            let row = y * self.width;
+            frame[row + rect.x..row + rect.right()]
+                .copy_from_slice(&pixels[row + rect.x..row + rect.right()]);
+        }
+        state.frames.push(frame);
+        state.regions.push(rect);
+    }
+}
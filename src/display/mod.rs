@@ -0,0 +1,86 @@
+use crate::rect::Rect;
+use std::error::Error;
+use std::fmt::Debug;
+use std::ops::Deref;
+use std::ops::DerefMut;
+use std::sync::MutexGuard;
+
+pub mod headless_display;
+
+pub trait Display: Debug + Send {
+    fn width(&self) -> usize;
+
+    fn height(&self) -> usize;
+
+    fn setup(&mut self) -> Result<(), Box<dyn Error>>;
+
+    fn shutdown(&mut self) -> Result<(), Box<dyn Error>>;
+
+    fn write_frame(&mut self, pixels: &[u32]);
+
+    /// Update only `rect` of the screen from a full frame of `pixels`.
+    /// Displays that can't do better write the whole frame.
+    fn write_region(&mut self, pixels: &[u32], rect: Rect) {
+        let _ = rect;
+        self.write_frame(pixels);
+    }
+
+    /// Show the regions written since the last call, for displays that
+    /// draw offscreen. Anything written with `write_frame` is shown at once.
+    fn present(&mut self) {}
+
+    /// Ask for frames to be drawn offscreen and flipped onto the screen,
+    /// taking effect on `setup`. Displays that can't are left as they are.
+    fn set_double_buffering(&mut self, enabled: bool) {
+        let _ = enabled;
+    }
+}
+
+/// Holds a display for the duration of a run and shuts it down when dropped,
+/// including while unwinding from a panic, so the terminal is never left in
+/// graphics mode.
+pub struct DisplayGuard<'a> {
+    display: MutexGuard<'a, Box<dyn Display>>,
+    active: bool,
+}
+
+impl<'a> DisplayGuard<'a> {
+    pub fn new(display: MutexGuard<'a, Box<dyn Display>>) -> Self {
+        DisplayGuard {
+            display,
+            active: true,
+        }
+    }
+
+    /// Shut the display down now rather than on drop. Only the first call
+    /// reaches the display.
+    pub fn shutdown(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.active {
+            return Ok(());
+        }
+        self.active = false;
+        self.display.shutdown()
+    }
+}
+
+impl<'a> Deref for DisplayGuard<'a> {
+    type Target = Box<dyn Display>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.display
+    }
+}
+
+impl<'a> DerefMut for DisplayGuard<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.display
+    }
+}
+
+impl<'a> Drop for DisplayGuard<'a> {
+    fn drop(&mut self) {
+        if let Err(err) = self.shutdown() {
+            eprintln!("Error occured shutting down display: {}", err);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::font::{Font, Glyph};
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Parse a Glyph Bitmap Distribution Format font.
+pub fn from_str(text: &str) -> Result<Font, Box<dyn Error>> {
+    let mut glyphs = HashMap::new();
+    let mut ascent = None;
+    let mut descent = None;
+    let mut bounding_box = (0, 0, 0, 0);
+
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("FONTBOUNDINGBOX") => bounding_box = four(words)?,
+            Some("FONT_ASCENT") => ascent = words.next().map(str::parse::<isize>).transpose()?,
+            Some("FONT_DESCENT") => descent = words.next().map(str::parse::<isize>).transpose()?,
+            Some("STARTCHAR") => {
+                let mut encoding = None;
+                let mut advance = None;
+                let mut bbx = bounding_box;
+                let mut rows = vec![];
+                while let Some(line) = lines.next() {
+                    let mut words = line.split_whitespace();
+                    match words.next() {
+                        Some("ENCODING") => {
+                            encoding = words.next().map(str::parse::<i64>).transpose()?
+                        }
+                        Some("DWIDTH") => {
+                            advance = words.next().map(str::parse::<isize>).transpose()?
+                        }
+                        Some("BBX") => bbx = four(words)?,
+                        Some("BITMAP") => {
+                            for row in lines.by_ref() {
+                                let row = row.trim();
+                                if row == "ENDCHAR" {
+                                    break;
+                                }
+                                rows.push(hex(row)?);
+                            }
+                            break;
+                        }
+                        Some("ENDCHAR") => break,
+                        _ => {}
+                    }
+                }
+                let c = match encoding
+                    .filter(|e| *e >= 0)
+                    .and_then(|e| std::char::from_u32(e as u32))
+                {
+                    Some(c) => c,
+                    // unencoded glyphs can't be reached from a string
+                    None => continue,
+                };
+                let (w, h, x_offset, y_offset) = bbx;
+                let mut glyph = Glyph::from_bits(w.max(0) as usize, h.max(0) as usize, &rows);
+                glyph.left = x_offset;
+                glyph.top = y_offset + h;
+                glyph.advance = advance.unwrap_or(w).max(0) as f32;
+                glyphs.insert(c, glyph);
+            }
+            _ => {}
+        }
+    }
+
+    let (_, h, _, y_offset) = bounding_box;
+    let ascent = ascent.unwrap_or(h + y_offset).max(0) as usize;
+    let descent = descent.unwrap_or(-y_offset).max(0) as usize;
+    Ok(Font::new(glyphs, ascent, ascent + descent))
+}
+
+fn four<'a>(
+    mut words: impl Iterator<Item = &'a str>,
+) -> Result<(isize, isize, isize, isize), Box<dyn Error>> {
+    let mut next = || -> Result<isize, Box<dyn Error>> {
+        Ok(words.next().ok_or("missing bounding box value")?.parse()?)
+    };
+    Ok((next()?, next()?, next()?, next()?))
+}
+
+fn hex(row: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    (0..row.len() / 2)
+        .map(|i| {
+            let digits = row.get(i * 2..i * 2 + 2).ok_or("bad BDF bitmap row")?;
+            Ok(u8::from_str_radix(digits, 16)?)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &str = "STARTFONT 2.1
+FONTBOUNDINGBOX 4 6 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 5
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 1
+STARTCHAR T
+ENCODING 84
+DWIDTH 5 0
+BBX 3 3 0 2
+BITMAP
+E0
+40
+40
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn parses_glyphs() {
+        let font = from_str(FONT).unwrap();
+        assert_eq!((font.ascent, font.line_height), (5, 6));
+        let t = font.glyph('T').unwrap();
+        assert_eq!((t.width, t.height, t.top, t.advance), (3, 3, 5, 5.0));
+        assert_eq!(t.coverage, vec![255, 255, 255, 0, 255, 0, 0, 255, 0]);
+    }
+
+    #[test]
+    fn rejects_non_ascii_bitmap_rows() {
+        assert!(hex("aé").is_err());
+        assert!(from_str(&FONT.replace("\n40\n40\n", "\naé\n40\n")).is_err());
+    }
+}
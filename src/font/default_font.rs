@@ -0,0 +1,130 @@
+use crate::font::{Font, Glyph};
+use std::collections::HashMap;
+
+const ADVANCE: usize = 6;
+const ASCENT: usize = 7;
+const LINE_HEIGHT: usize = 9;
+
+// 5 pixel wide glyphs for printable ASCII. The first seven rows sit on the
+// baseline, an eighth row holds descenders.
+#[rustfmt::skip]
+const GLYPHS: &[(char, &[&str])] = &[
+    (' ', &[".....", ".....", ".....", ".....", ".....", ".....", "....."]),
+    ('!', &["..#..", "..#..", "..#..", "..#..", "..#..", ".....", "..#.."]),
+    ('"', &[".#.#.", ".#.#.", ".....", ".....", ".....", ".....", "....."]),
+    ('#', &[".#.#.", ".#.#.", "#####", ".#.#.", "#####", ".#.#.", ".#.#."]),
+    ('$', &["..#..", ".####", "#.#..", ".###.", "..#.#", "####.", "..#.."]),
+    ('%', &["##...", "##..#", "...#.", "..#..", ".#...", "#..##", "...##"]),
+    ('&', &[".##..", "#..#.", "#.#..", ".#...", "#.#.#", "#..#.", ".##.#"]),
+    ('\'', &["..#..", "..#..", ".#...", ".....", ".....", ".....", "....."]),
+    ('(', &["...#.", "..#..", ".#...", ".#...", ".#...", "..#..", "...#."]),
+    (')', &[".#...", "..#..", "...#.", "...#.", "...#.", "..#..", ".#..."]),
+    ('*', &[".....", "..#..", "#.#.#", ".###.", "#.#.#", "..#..", "....."]),
+    ('+', &[".....", "..#..", "..#..", "#####", "..#..", "..#..", "....."]),
+    (',', &[".....", ".....", ".....", ".....", ".##..", "..#..", ".#..."]),
+    ('-', &[".....", ".....", ".....", "#####", ".....", ".....", "....."]),
+    ('.', &[".....", ".....", ".....", ".....", ".....", ".##..", ".##.."]),
+    ('/', &[".....", "....#", "...#.", "..#..", ".#...", "#....", "....."]),
+    ('0', &[".###.", "#...#", "#..##", "#.#.#", "##..#", "#...#", ".###."]),
+    ('1', &["..#..", ".##..", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('2', &[".###.", "#...#", "....#", "...#.", "..#..", ".#...", "#####"]),
+    ('3', &["#####", "...#.", "..#..", "...#.", "....#", "#...#", ".###."]),
+    ('4', &["...#.", "..##.", ".#.#.", "#..#.", "#####", "...#.", "...#."]),
+    ('5', &["#####", "#....", "####.", "....#", "....#", "#...#", ".###."]),
+    ('6', &["..##.", ".#...", "#....", "####.", "#...#", "#...#", ".###."]),
+    ('7', &["#####", "....#", "...#.", "..#..", ".#...", ".#...", ".#..."]),
+    ('8', &[".###.", "#...#", "#...#", ".###.", "#...#", "#...#", ".###."]),
+    ('9', &[".###.", "#...#", "#...#", ".####", "....#", "...#.", ".##.."]),
+    (':', &[".....", ".##..", ".##..", ".....", ".##..", ".##..", "....."]),
+    (';', &[".....", ".##..", ".##..", ".....", ".##..", "..#..", ".#..."]),
+    ('<', &["...#.", "..#..", ".#...", "#....", ".#...", "..#..", "...#."]),
+    ('=', &[".....", ".....", "#####", ".....", "#####", ".....", "....."]),
+    ('>', &[".#...", "..#..", "...#.", "....#", "...#.", "..#..", ".#..."]),
+    ('?', &[".###.", "#...#", "....#", "...#.", "..#..", ".....", "..#.."]),
+    ('@', &[".###.", "#...#", "....#", ".##.#", "#.#.#", "#.#.#", ".###."]),
+    ('A', &[".###.", "#...#", "#...#", "#####", "#...#", "#...#", "#...#"]),
+    ('B', &["####.", "#...#", "#...#", "####.", "#...#", "#...#", "####."]),
+    ('C', &[".###.", "#...#", "#....", "#....", "#....", "#...#", ".###."]),
+    ('D', &["###..", "#..#.", "#...#", "#...#", "#...#", "#..#.", "###.."]),
+    ('E', &["#####", "#....", "#....", "####.", "#....", "#....", "#####"]),
+    ('F', &["#####", "#....", "#....", "####.", "#....", "#....", "#...."]),
+    ('G', &[".###.", "#...#", "#....", "#.###", "#...#", "#...#", ".####"]),
+    ('H', &["#...#", "#...#", "#...#", "#####", "#...#", "#...#", "#...#"]),
+    ('I', &[".###.", "..#..", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('J', &["..###", "...#.", "...#.", "...#.", "...#.", "#..#.", ".##.."]),
+    ('K', &["#...#", "#..#.", "#.#..", "##...", "#.#..", "#..#.", "#...#"]),
+    ('L', &["#....", "#....", "#....", "#....", "#....", "#....", "#####"]),
+    ('M', &["#...#", "##.##", "#.#.#", "#.#.#", "#...#", "#...#", "#...#"]),
+    ('N', &["#...#", "#...#", "##..#", "#.#.#", "#..##", "#...#", "#...#"]),
+    ('O', &[".###.", "#...#", "#...#", "#...#", "#...#", "#...#", ".###."]),
+    ('P', &["####.", "#...#", "#...#", "####.", "#....", "#....", "#...."]),
+    ('Q', &[".###.", "#...#", "#...#", "#...#", "#.#.#", "#..#.", ".##.#"]),
+    ('R', &["####.", "#...#", "#...#", "####.", "#.#..", "#..#.", "#...#"]),
+    ('S', &[".####", "#....", "#....", ".###.", "....#", "....#", "####."]),
+    ('T', &["#####", "..#..", "..#..", "..#..", "..#..", "..#..", "..#.."]),
+    ('U', &["#...#", "#...#", "#...#", "#...#", "#...#", "#...#", ".###."]),
+    ('V', &["#...#", "#...#", "#...#", "#...#", "#...#", ".#.#.", "..#.."]),
+    ('W', &["#...#", "#...#", "#...#", "#.#.#", "#.#.#", "#.#.#", ".#.#."]),
+    ('X', &["#...#", "#...#", ".#.#.", "..#..", ".#.#.", "#...#", "#...#"]),
+    ('Y', &["#...#", "#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', &["#####", "....#", "...#.", "..#..", ".#...", "#....", "#####"]),
+    ('[', &[".###.", ".#...", ".#...", ".#...", ".#...", ".#...", ".###."]),
+    ('\\', &[".....", "#....", ".#...", "..#..", "...#.", "....#", "....."]),
+    (']', &[".###.", "...#.", "...#.", "...#.", "...#.", "...#.", ".###."]),
+    ('^', &["..#..", ".#.#.", "#...#", ".....", ".....", ".....", "....."]),
+    ('_', &[".....", ".....", ".....", ".....", ".....", ".....", "#####"]),
+    ('`', &[".#...", "..#..", "...#.", ".....", ".....", ".....", "....."]),
+    ('a', &[".....", ".....", ".###.", "....#", ".####", "#...#", ".####"]),
+    ('b', &["#....", "#....", "#.##.", "##..#", "#...#", "#...#", "####."]),
+    ('c', &[".....", ".....", ".###.", "#....", "#....", "#...#", ".###."]),
+    ('d', &["....#", "....#", ".##.#", "#..##", "#...#", "#...#", ".####"]),
+    ('e', &[".....", ".....", ".###.", "#...#", "#####", "#....", ".###."]),
+    ('f', &["..##.", ".#..#", ".#...", "###..", ".#...", ".#...", ".#..."]),
+    ('g', &[".....", ".....", ".####", "#...#", "#...#", ".####", "....#", ".###."]),
+    ('h', &["#....", "#....", "#.##.", "##..#", "#...#", "#...#", "#...#"]),
+    ('i', &["..#..", ".....", ".##..", "..#..", "..#..", "..#..", ".###."]),
+    ('j', &["...#.", ".....", "..##.", "...#.", "...#.", "...#.", "#..#.", ".##.."]),
+    ('k', &["#....", "#....", "#..#.", "#.#..", "##...", "#.#..", "#..#."]),
+    ('l', &[".##..", "..#..", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('m', &[".....", ".....", "##.#.", "#.#.#", "#.#.#", "#...#", "#...#"]),
+    ('n', &[".....", ".....", "#.##.", "##..#", "#...#", "#...#", "#...#"]),
+    ('o', &[".....", ".....", ".###.", "#...#", "#...#", "#...#", ".###."]),
+    ('p', &[".....", ".....", "####.", "#...#", "#...#", "####.", "#....", "#...."]),
+    ('q', &[".....", ".....", ".##.#", "#..##", "#...#", ".####", "....#", "....#"]),
+    ('r', &[".....", ".....", "#.##.", "##..#", "#....", "#....", "#...."]),
+    ('s', &[".....", ".....", ".###.", "#....", ".###.", "....#", "####."]),
+    ('t', &[".#...", ".#...", "###..", ".#...", ".#...", ".#..#", "..##."]),
+    ('u', &[".....", ".....", "#...#", "#...#", "#...#", "#..##", ".##.#"]),
+    ('v', &[".....", ".....", "#...#", "#...#", "#...#", ".#.#.", "..#.."]),
+    ('w', &[".....", ".....", "#...#", "#...#", "#.#.#", "#.#.#", ".#.#."]),
+    ('x', &[".....", ".....", "#...#", ".#.#.", "..#..", ".#.#.", "#...#"]),
+    ('y', &[".....", ".....", "#...#", "#...#", "#...#", ".####", "....#", ".###."]),
+    ('z', &[".....", ".....", "#####", "...#.", "..#..", ".#...", "#####"]),
+    ('{', &["...#.", "..#..", "..#..", ".#...", "..#..", "..#..", "...#."]),
+    ('|', &["..#..", "..#..", "..#..", "..#..", "..#..", "..#..", "..#.."]),
+    ('}', &[".#...", "..#..", "..#..", "...#.", "..#..", "..#..", ".#..."]),
+    ('~', &[".....", ".....", ".#...", "#.#.#", "...#.", ".....", "....."]),
+];
+
+pub fn font() -> Font {
+    let glyphs: HashMap<char, Glyph> = GLYPHS
+        .iter()
+        .map(|(c, rows)| {
+            let width = rows[0].len();
+            let coverage = rows
+                .iter()
+                .flat_map(|row| row.bytes().map(|b| if b == b'#' { 255 } else { 0 }))
+                .collect();
+            let glyph = Glyph {
+                width,
+                height: rows.len(),
+                left: 0,
+                top: ASCENT as isize,
+                advance: ADVANCE as f32,
+                coverage,
+            };
+            (*c, glyph)
+        })
+        .collect();
+    Font::new(glyphs, ASCENT, LINE_HEIGHT)
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+pub mod bdf;
+mod default_font;
+pub mod psf;
+pub mod truetype;
+
+/// A single character's image, positioned relative to the pen on the baseline.
+#[derive(Debug, Clone, Default)]
+pub struct Glyph {
+    pub width: usize,
+    pub height: usize,
+    // offset from the pen to the left edge of the image
+    pub left: isize,
+    // distance from the baseline up to the top edge of the image
+    pub top: isize,
+    // how far the pen moves right after drawing this glyph
+    pub advance: f32,
+    // how much of each pixel is covered, 0 for none through 255 for all of it
+    pub coverage: Vec<u8>,
+}
+
+impl Glyph {
+    /// Build a glyph from rows of bits, most significant bit leftmost, such as
+    /// the bitmaps found in PSF and BDF fonts.
+    pub fn from_bits(width: usize, height: usize, rows: &[Vec<u8>]) -> Self {
+        let mut coverage = vec![0; width * height];
+        for (y, row) in rows.iter().take(height).enumerate() {
+            for x in 0..width {
+                if row.get(x / 8).is_some_and(|b| b & (0x80 >> (x % 8)) != 0) {
+                    coverage[y * width + x] = 255;
+                }
+            }
+        }
+        Glyph {
+            width,
+            height,
+            left: 0,
+            top: height as isize,
+            advance: width as f32,
+            coverage,
+        }
+    }
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// console fonts are usually shipped gzipped
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+    const FHCRC: u8 = 0x02;
+    let truncated = "gzip header is truncated";
+    let flags = *bytes.get(3).ok_or(truncated)?;
+    let mut i = 10;
+    if flags & FEXTRA != 0 {
+        let len = bytes.get(i..i + 2).ok_or(truncated)?;
+        i += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+    }
+    for flag in [FNAME, FCOMMENT].iter() {
+        if flags & flag != 0 {
+            let end = bytes
+                .get(i..)
+                .ok_or(truncated)?
+                .iter()
+                .position(|b| *b == 0);
+            i += end.ok_or(truncated)? + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        i += 2;
+    }
+    let data = bytes.get(i..).ok_or(truncated)?;
+    miniz_oxide::inflate::decompress_to_vec(data)
+        .map_err(|status| format!("could not decompress font: {:?}", status).into())
+}
+
+/// A glyph placed by `Font::layout`, relative to the top left of the text.
+#[derive(Debug, Clone)]
+pub struct PositionedGlyph {
+    // top left corner of the glyph's image
+    pub x: isize,
+    pub y: isize,
+    pub glyph: Arc<Glyph>,
+}
+
+#[derive(Debug)]
+pub struct Font {
+    // every glyph of a bitmap font, or those rasterized so far of a TrueType font
+    glyphs: Mutex<HashMap<char, Arc<Glyph>>>,
+    outlines: Option<truetype::Outlines>,
+    // distance from the top of a line down to its baseline
+    pub ascent: usize,
+    // distance from the top of one line to the top of the next
+    pub line_height: usize,
+}
+
+impl Clone for Font {
+    fn clone(&self) -> Self {
+        Font {
+            glyphs: Mutex::new(self.glyphs.lock().unwrap().clone()),
+            outlines: self.outlines.clone(),
+            ascent: self.ascent,
+            line_height: self.line_height,
+        }
+    }
+}
+
+impl Default for Font {
+    /// A small built in monospace font covering printable ASCII.
+    fn default() -> Self {
+        default_font::font()
+    }
+}
+
+impl Font {
+    pub fn new(glyphs: HashMap<char, Glyph>, ascent: usize, line_height: usize) -> Self {
+        Font {
+            glyphs: Mutex::new(glyphs.into_iter().map(|(c, g)| (c, Arc::new(g))).collect()),
+            outlines: None,
+            ascent,
+            line_height,
+        }
+    }
+
+    /// Load a PSF (optionally gzipped, as in `/usr/share/consolefonts`) or BDF
+    /// font. TrueType and OpenType fonts are loaded at
+    /// `truetype::DEFAULT_SIZE` pixels, see `load_truetype` to choose a size.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let mut bytes = std::fs::read(path)?;
+        if bytes.starts_with(&GZIP_MAGIC) {
+            bytes = gunzip(&bytes)?;
+        }
+        if psf::is_psf(&bytes) {
+            psf::from_bytes(&bytes)
+        } else if truetype::is_truetype(&bytes) {
+            truetype::from_bytes(bytes, truetype::DEFAULT_SIZE)
+        } else {
+            bdf::from_str(std::str::from_utf8(&bytes)?)
+        }
+    }
+
+    /// Load a TrueType or OpenType font rendered `size` pixels tall.
+    pub fn load_truetype<P: AsRef<Path>>(path: P, size: f32) -> Result<Self, Box<dyn Error>> {
+        truetype::from_bytes(std::fs::read(path)?, size)
+    }
+
+    /// The same font at another pixel size, sharing the loaded font data.
+    /// Bitmap fonts can't be scaled and come back unchanged.
+    pub fn resized(&self, size: f32) -> Self {
+        match &self.outlines {
+            Some(outlines) => truetype::with_outlines(outlines.resized(size)),
+            None => self.clone(),
+        }
+    }
+
+    /// The glyph to draw for `c`, falling back to `?` for missing characters
+    /// of bitmap fonts.
+    pub fn glyph(&self, c: char) -> Option<Arc<Glyph>> {
+        let mut glyphs = self.glyphs.lock().unwrap();
+        if let Some(glyph) = glyphs.get(&c) {
+            return Some(glyph.clone());
+        }
+        match &self.outlines {
+            Some(outlines) => {
+                let glyph = Arc::new(outlines.rasterize(c));
+                glyphs.insert(c, glyph.clone());
+                Some(glyph)
+            }
+            None => glyphs.get(&'?').cloned(),
+        }
+    }
+
+    /// How much closer (negative) or further apart `right` should be drawn
+    /// after `left` than their advance alone would place it.
+    pub fn kerning(&self, left: char, right: char) -> f32 {
+        match &self.outlines {
+            Some(outlines) => outlines.kerning(left, right),
+            None => 0.0,
+        }
+    }
+
+    /// Place each glyph of `text`, starting a new line at every newline.
+    pub fn layout(&self, text: &str) -> Vec<PositionedGlyph> {
+        let mut positioned = vec![];
+        for (i, line) in text.split('\n').enumerate() {
+            let baseline = (i * self.line_height + self.ascent) as isize;
+            self.layout_line(line, |pen, glyph| {
+                positioned.push(PositionedGlyph {
+                    x: pen.round() as isize + glyph.left,
+                    y: baseline - glyph.top,
+                    glyph,
+                })
+            });
+        }
+        positioned
+    }
+
+    // walk the glyphs of a line with the pen position of each, returning the
+    // line's width
+    fn layout_line(&self, line: &str, mut f: impl FnMut(f32, Arc<Glyph>)) -> f32 {
+        let mut pen = 0.0;
+        let mut previous = None;
+        for c in line.chars() {
+            let glyph = match self.glyph(c) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            if let Some(p) = previous {
+                pen += self.kerning(p, c);
+            }
+            let advance = glyph.advance;
+            f(pen, glyph);
+            pen += advance;
+            previous = Some(c);
+        }
+        pen
+    }
+
+    /// The width of the widest line and the height of all lines of `text`.
+    pub fn measure(&self, text: &str) -> (usize, usize) {
+        let mut width: f32 = 0.0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            width = width.max(self.layout_line(line, |_, _| {}));
+            lines += 1;
+        }
+        (width.ceil() as usize, lines * self.line_height)
+    }
+
+    /// Break `text` into lines at spaces so none is wider than `max_width`,
+    /// where possible. Existing newlines are kept.
+    pub fn wrap(&self, text: &str, max_width: usize) -> String {
+        let mut wrapped = String::new();
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                wrapped.push('\n');
+            }
+            let mut current = String::new();
+            for word in line.split(' ') {
+                let candidate = if current.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{} {}", current, word)
+                };
+                if !current.is_empty() && self.measure(&candidate).0 > max_width {
+                    wrapped.push_str(&current);
+                    wrapped.push('\n');
+                    current = word.to_string();
+                } else {
+                    current = candidate;
+                }
+            }
+            wrapped.push_str(&current);
+        }
+        wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_lines() {
+        let font = Font::default();
+        assert_eq!(font.measure("ab"), (12, 9));
+        assert_eq!(font.measure("abc\nd"), (18, 18));
+        assert_eq!(font.measure(""), (0, 9));
+    }
+
+    #[test]
+    fn missing_characters_fall_back() {
+        let font = Font::default();
+        let question = font.glyph('?').unwrap().coverage.clone();
+        assert_eq!(font.glyph('\u{2603}').unwrap().coverage, question);
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        let font = Font::default();
+        // six pixels per character
+        assert_eq!(font.wrap("one two three\nfour", 48), "one two\nthree\nfour");
+        assert_eq!(font.wrap("unbreakable", 12), "unbreakable");
+    }
+
+    #[test]
+    fn layout_places_lines() {
+        let font = Font::default();
+        let glyphs = font.layout("ab\nc");
+        let positions: Vec<(isize, isize)> = glyphs.iter().map(|g| (g.x, g.y)).collect();
+        assert_eq!(positions, vec![(0, 0), (6, 0), (0, 9)]);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::font::{Font, Glyph};
+use std::collections::HashMap;
+use std::error::Error;
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE512: u8 = 0x01;
+const PSF1_MODEHASTAB: u8 = 0x02;
+const PSF1_MODEHASSEQ: u8 = 0x04;
+const PSF1_SEPARATOR: u16 = 0xffff;
+const PSF1_STARTSEQ: u16 = 0xfffe;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+const PSF2_SEPARATOR: u8 = 0xff;
+const PSF2_STARTSEQ: u8 = 0xfe;
+
+pub fn is_psf(bytes: &[u8]) -> bool {
+    bytes.starts_with(&PSF1_MAGIC) || bytes.starts_with(&PSF2_MAGIC)
+}
+
+/// Parse a PC Screen Font, version 1 or 2, as used by the Linux console.
+/// Fonts without a unicode table map glyph `n` to the character `n`.
+pub fn from_bytes(bytes: &[u8]) -> Result<Font, Box<dyn Error>> {
+    if bytes.starts_with(&PSF1_MAGIC) {
+        psf1(bytes)
+    } else if bytes.starts_with(&PSF2_MAGIC) {
+        psf2(bytes)
+    } else {
+        Err("not a PSF font".into())
+    }
+}
+
+fn psf1(bytes: &[u8]) -> Result<Font, Box<dyn Error>> {
+    if bytes.len() < 4 {
+        return Err("PSF1 header is truncated".into());
+    }
+    let mode = bytes[2];
+    let height = bytes[3] as usize;
+    let count = if mode & PSF1_MODE512 != 0 { 512 } else { 256 };
+    let glyphs = read_glyphs(&bytes[4..], count, 8, height)?;
+
+    let mut chars: Vec<Vec<char>> = vec![vec![]; count];
+    if mode & (PSF1_MODEHASTAB | PSF1_MODEHASSEQ) != 0 {
+        let table = &bytes[4 + count * height..];
+        let mut index = 0;
+        let mut in_sequence = false;
+        for entry in table.chunks_exact(2) {
+            if index >= count {
+                break;
+            }
+            match u16::from_le_bytes([entry[0], entry[1]]) {
+                PSF1_SEPARATOR => {
+                    index += 1;
+                    in_sequence = false;
+                }
+                PSF1_STARTSEQ => in_sequence = true,
+                code if !in_sequence => {
+                    if let Some(c) = std::char::from_u32(code as u32) {
+                        chars[index].push(c);
+                    }
+                }
+                _ => {}
+            }
+        }
+    } else {
+        identity_table(&mut chars);
+    }
+    Ok(build(glyphs, chars, height))
+}
+
+fn psf2(bytes: &[u8]) -> Result<Font, Box<dyn Error>> {
+    if bytes.len() < 32 {
+        return Err("PSF2 header is truncated".into());
+    }
+    let field = |i: usize| {
+        u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]) as usize
+    };
+    let header_size = field(8);
+    let flags = field(12) as u32;
+    let count = field(16);
+    let char_size = field(20);
+    let height = field(24);
+    let width = field(28);
+    let min_char_size = height.checked_mul(width.div_ceil(8));
+    if char_size == 0
+        || min_char_size.is_none_or(|min| char_size < min)
+        || header_size > bytes.len()
+    {
+        return Err("PSF2 header is inconsistent".into());
+    }
+    let glyph_bytes = &bytes[header_size..];
+    // check the count against the data before allocating anything for it
+    match count.checked_mul(char_size) {
+        Some(len) if len <= glyph_bytes.len() => {}
+        _ => return Err("PSF2 glyph data is truncated".into()),
+    }
+    let glyphs: Vec<Glyph> = glyph_bytes
+        .chunks_exact(char_size)
+        .take(count)
+        .map(|data| read_glyph(data, width, height))
+        .collect();
+
+    let mut chars: Vec<Vec<char>> = vec![vec![]; count];
+    if flags & PSF2_HAS_UNICODE_TABLE != 0 {
+        let table = &glyph_bytes[(count * char_size).min(glyph_bytes.len())..];
+        let mut index = 0;
+        let mut start = 0;
+        let mut in_sequence = false;
+        for (i, b) in table.iter().enumerate() {
+            if index >= count {
+                break;
+            }
+            if *b == PSF2_SEPARATOR || *b == PSF2_STARTSEQ {
+                if !in_sequence {
+                    if let Ok(s) = std::str::from_utf8(&table[start..i]) {
+                        chars[index].extend(s.chars());
+                    }
+                }
+                if *b == PSF2_SEPARATOR {
+                    index += 1;
+                    in_sequence = false;
+                } else {
+                    in_sequence = true;
+                }
+                start = i + 1;
+            }
+        }
+    } else {
+        identity_table(&mut chars);
+    }
+    Ok(build(glyphs, chars, height))
+}
+
+fn read_glyphs(
+    data: &[u8],
+    count: usize,
+    width: usize,
+    height: usize,
+) -> Result<Vec<Glyph>, Box<dyn Error>> {
+    let row_bytes = width.div_ceil(8);
+    let char_size = row_bytes * height;
+    if data.len() < count * char_size {
+        return Err("PSF glyph data is truncated".into());
+    }
+    Ok(data
+        .chunks_exact(char_size)
+        .take(count)
+        .map(|d| read_glyph(d, width, height))
+        .collect())
+}
+
+fn read_glyph(data: &[u8], width: usize, height: usize) -> Glyph {
+    let row_bytes = width.div_ceil(8);
+    let rows: Vec<Vec<u8>> = data
+        .chunks_exact(row_bytes)
+        .take(height)
+        .map(|r| r.to_vec())
+        .collect();
+    Glyph::from_bits(width, height, &rows)
+}
+
+fn identity_table(chars: &mut [Vec<char>]) {
+    for (i, c) in chars.iter_mut().enumerate() {
+        if let Some(ch) = std::char::from_u32(i as u32) {
+            c.push(ch);
+        }
+    }
+}
+
+fn build(glyphs: Vec<Glyph>, chars: Vec<Vec<char>>, height: usize) -> Font {
+    let mut map = HashMap::new();
+    for (glyph, cs) in glyphs.into_iter().zip(chars) {
+        for c in cs {
+            map.entry(c).or_insert_with(|| glyph.clone());
+        }
+    }
+    // console fonts have no baseline, keep a couple of rows for descenders
+    let ascent = height - height / 4;
+    for glyph in map.values_mut() {
+        glyph.top = ascent as isize;
+    }
+    Font::new(map, ascent, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn psf2_font(unicode: Option<&[u8]>) -> Vec<u8> {
+        let mut bytes = PSF2_MAGIC.to_vec();
+        let flags = if unicode.is_some() { 1 } else { 0 };
+        // version, header size, flags, glyph count, bytes per glyph, height, width
+        for v in [0u32, 32, flags, 2, 4, 4, 3].iter() {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0b1110_0000, 0b1010_0000, 0b1010_0000, 0b1110_0000]);
+        bytes.extend_from_slice(&[0b0100_0000, 0b0100_0000, 0b0100_0000, 0b0100_0000]);
+        if let Some(table) = unicode {
+            bytes.extend_from_slice(table);
+        }
+        bytes
+    }
+
+    #[test]
+    fn psf2_without_table_maps_by_index() {
+        let font = from_bytes(&psf2_font(None)).unwrap();
+        let zero = font.glyph('\u{0}').unwrap();
+        assert_eq!((zero.width, zero.height, zero.advance), (3, 4, 3.0));
+        assert_eq!(&zero.coverage[3..6], &[255, 0, 255]);
+        assert_eq!(font.glyph('\u{1}').unwrap().coverage[1], 255);
+    }
+
+    #[test]
+    fn psf2_unicode_table() {
+        let table = b"O\xffl|\xfe|-\xff";
+        let font = from_bytes(&psf2_font(Some(table))).unwrap();
+        assert_eq!(font.glyph('O').unwrap().coverage[3], 255);
+        assert_eq!(font.glyph('l').unwrap().coverage[1], 255);
+        assert_eq!(font.glyph('|').unwrap().coverage[1], 255);
+        assert!(font.glyph('-').is_none());
+    }
+
+    #[test]
+    fn psf2_rejects_impossible_headers() {
+        let mut empty = psf2_font(None);
+        empty[20..24].copy_from_slice(&0u32.to_le_bytes());
+        empty[24..28].copy_from_slice(&0u32.to_le_bytes());
+        empty[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(from_bytes(&empty[..32]).is_err());
+
+        let mut truncated = psf2_font(None);
+        truncated[16..20].copy_from_slice(&3u32.to_le_bytes());
+        assert!(from_bytes(&truncated).is_err());
+    }
+
+    #[test]
+    fn psf1_with_table() {
+        let mut bytes = PSF1_MAGIC.to_vec();
+        bytes.extend_from_slice(&[PSF1_MODEHASTAB, 2]);
+        let mut glyphs = vec![0; 256 * 2];
+        glyphs[2] = 0b1000_0001;
+        bytes.extend_from_slice(&glyphs);
+        for i in 0..256u16 {
+            let code = if i == 1 { 'x' as u16 } else { 0x2000 + i };
+            bytes.extend_from_slice(&code.to_le_bytes());
+            bytes.extend_from_slice(&PSF1_SEPARATOR.to_le_bytes());
+        }
+        let font = from_bytes(&bytes).unwrap();
+        let x = font.glyph('x').unwrap();
+        assert_eq!((x.width, x.height), (8, 2));
+        assert_eq!(x.coverage[0], 255);
+        assert_eq!(x.coverage[7], 255);
+        assert_eq!(x.coverage[8], 0);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::font::{Font, Glyph};
+use rusttype::{point, Scale};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Mutex;
+
+/// The pixel size `Font::load` renders TrueType fonts at.
+pub const DEFAULT_SIZE: f32 = 16.0;
+
+// version tags a TrueType or OpenType font file can start with
+const MAGICS: [[u8; 4]; 4] = [[0, 1, 0, 0], *b"true", *b"OTTO", *b"ttcf"];
+
+pub fn is_truetype(bytes: &[u8]) -> bool {
+    MAGICS.iter().any(|magic| bytes.starts_with(magic))
+}
+
+/// Scalable outlines glyphs are rasterized from, with antialiasing, as they
+/// are first drawn.
+#[derive(Clone)]
+pub struct Outlines {
+    font: rusttype::Font<'static>,
+    scale: Scale,
+}
+
+impl fmt::Debug for Outlines {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Outlines")
+            .field("glyphs", &self.font.glyph_count())
+            .field("size", &self.scale.y)
+            .finish()
+    }
+}
+
+impl Outlines {
+    pub fn resized(&self, size: f32) -> Self {
+        Outlines {
+            font: self.font.clone(),
+            scale: Scale::uniform(size),
+        }
+    }
+
+    pub fn rasterize(&self, c: char) -> Glyph {
+        let glyph = self.font.glyph(c).scaled(self.scale);
+        let advance = glyph.h_metrics().advance_width;
+        let glyph = glyph.positioned(point(0.0, 0.0));
+        let bounds = match glyph.pixel_bounding_box() {
+            Some(bounds) => bounds,
+            // nothing to draw, as for a space
+            None => {
+                return Glyph {
+                    advance,
+                    ..Default::default()
+                }
+            }
+        };
+        let width = bounds.width() as usize;
+        let height = bounds.height() as usize;
+        let mut coverage = vec![0; width * height];
+        glyph.draw(|x, y, v| {
+            coverage[y as usize * width + x as usize] = (v * 255.0).round() as u8;
+        });
+        Glyph {
+            width,
+            height,
+            left: bounds.min.x as isize,
+            top: -bounds.min.y as isize,
+            advance,
+            coverage,
+        }
+    }
+
+    pub fn kerning(&self, left: char, right: char) -> f32 {
+        self.font.pair_kerning(self.scale, left, right)
+    }
+}
+
+/// Load a TrueType or OpenType font rendered `size` pixels tall.
+pub fn from_bytes(bytes: Vec<u8>, size: f32) -> Result<Font, Box<dyn Error>> {
+    let font = rusttype::Font::try_from_vec(bytes).ok_or("could not parse TrueType font")?;
+    Ok(with_outlines(Outlines {
+        font,
+        scale: Scale::uniform(size),
+    }))
+}
+
+pub(crate) fn with_outlines(outlines: Outlines) -> Font {
+    let metrics = outlines.font.v_metrics(outlines.scale);
+    let ascent = metrics.ascent.ceil().max(0.0) as usize;
+    let line_height = (metrics.ascent - metrics.descent + metrics.line_gap)
+        .ceil()
+        .max(1.0) as usize;
+    Font {
+        glyphs: Mutex::new(HashMap::new()),
+        outlines: Some(outlines),
+        ascent,
+        line_height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // made by tests/fonts/make_test_font.py: a space, a round "o", and "A"
+    // and "V" kerned together by 150 of the font's 1000 units
+    const TEST_FONT: &[u8] = include_bytes!("../../tests/fonts/test.ttf");
+
+    fn test_font(size: f32) -> Font {
+        from_bytes(TEST_FONT.to_vec(), size).unwrap()
+    }
+
+    #[test]
+    fn rasterizes_with_antialiasing() {
+        let font = test_font(20.0);
+        assert_eq!(font.ascent, 16);
+        assert_eq!(font.line_height, 20);
+        let o = font.glyph('o').unwrap();
+        assert_eq!((o.width, o.height, o.left, o.top), (10, 10, 1, 10));
+        assert_eq!(o.advance, 12.0);
+        // the edges of a curve are partially covered
+        assert!(o.coverage.iter().any(|&c| c > 0 && c < 255));
+        assert_eq!(o.coverage[5 * 10 + 5], 255);
+        let space = font.glyph(' ').unwrap();
+        assert!(space.coverage.is_empty());
+        assert_eq!(space.advance, 6.0);
+    }
+
+    #[test]
+    fn scales_with_size() {
+        let small = test_font(10.0);
+        let large = small.resized(40.0);
+        assert_eq!((small.line_height, large.line_height), (10, 40));
+        assert_eq!(small.measure("o o").0, 15);
+        assert_eq!(large.measure("o o").0, 60);
+    }
+
+    #[test]
+    fn kerns_pairs() {
+        let font = test_font(20.0);
+        assert_eq!(font.kerning('A', 'V'), -3.0);
+        assert_eq!(font.kerning('V', 'A'), -3.0);
+        assert_eq!(font.kerning('A', 'o'), 0.0);
+        let (a, v) = (font.measure("A").0, font.measure("V").0);
+        assert_eq!(font.measure("AV").0, a + v - 3);
+        assert_eq!(font.measure("Ao").0, a + font.measure("o").0);
+        // the V is drawn tucked under the A
+        let glyphs = font.layout("AV");
+        assert_eq!(glyphs[1].x - glyphs[0].x, 9);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(is_truetype(TEST_FONT));
+        assert!(!is_truetype(b"STARTFONT 2.1"));
+        assert!(from_bytes(vec![0, 1, 0, 0, 1, 2, 3], 12.0).is_err());
+    }
+}
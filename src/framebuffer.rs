@@ -1,64 +1,303 @@
+use crate::canvas::Canvas;
+use crate::display::Display;
+use crate::page_flip::PageFlip;
+use crate::pixel_format::PixelFormat;
+use crate::rect::Rect;
+use memmap::MmapOptions;
 use std::error::Error;
+use std::os::unix::io::AsRawFd;
 use std::path::Path;
 
 #[derive(Debug)]
 pub struct Framebuffer {
     fb: framebuffer::Framebuffer,
+    format: PixelFormat,
+    // screen info from before setup, put back on shutdown
+    saved_screen_info: Option<framebuffer::VarScreeninfo>,
+    // page flipping is tried on setup when asked for
+    double_buffering: bool,
+    // the page being drawn to while the other is shown, when flipping
+    pages: Option<PageFlip>,
+    // whether the driver has answered FBIO_WAITFORVSYNC so far
+    vsync: bool,
 }
 const FB_ACTIVATE_NOW: u32 = 0;
 const FB_ACTIVATE_FORCE: u32 = 128;
+const FBIOPAN_DISPLAY: u32 = 0x4606;
+// _IOW('F', 0x20, __u32)
+const FBIO_WAITFORVSYNC: u32 = 0x4004_4620;
 
 impl Framebuffer {
     pub fn new<P: AsRef<Path>>(path_to_framebuffer: P) -> Result<Self, Box<dyn Error>> {
+        let fb = framebuffer::Framebuffer::new(path_to_framebuffer)?;
+        let format = PixelFormat::from_var_screeninfo(&fb.var_screen_info);
         Ok(Framebuffer {
-            fb: framebuffer::Framebuffer::new(path_to_framebuffer)?,
+            fb,
+            format,
+            saved_screen_info: None,
+            double_buffering: false,
+            pages: None,
+            vsync: true,
         })
     }
 
     pub fn auto() -> Result<Self, Box<dyn Error>> {
-        Ok(Framebuffer {
-            fb: framebuffer::Framebuffer::new("/dev/fb0")?,
-        })
+        Framebuffer::new("/dev/fb0")
     }
 
-    pub fn setup(&self) -> Result<(), Box<dyn Error>> {
+    pub fn width(&self) -> usize {
+        self.fb.var_screen_info.xres as usize
+    }
+
+    pub fn height(&self) -> usize {
+        self.fb.var_screen_info.yres as usize
+    }
+
+    pub fn setup(&mut self) -> Result<(), Box<dyn Error>> {
         framebuffer::Framebuffer::set_kd_mode(framebuffer::KdMode::Graphics)?;
         // force the framebuffer to activate
         // https://unix.stackexchange.com/questions/58420/writes-to-framebuffer-dev-fb0-do-not-seem-to-change-graphics-screen
         let mut screen = framebuffer::Framebuffer::get_var_screeninfo(&self.fb.device)?;
+        self.saved_screen_info = Some(screen.clone());
         screen.activate |= FB_ACTIVATE_NOW | FB_ACTIVATE_FORCE;
         framebuffer::Framebuffer::put_var_screeninfo(&self.fb.device, &screen)?;
+        if self.double_buffering {
+            match self.enable_page_flipping() {
+                Ok(()) => {
+                    let page_len = self.line_length() * self.height();
+                    self.pages = Some(PageFlip::new(page_len, self.width(), self.height()));
+                }
+                Err(_) => {
+                    // carry on with the single page we were set up with
+                    framebuffer::Framebuffer::put_var_screeninfo(&self.fb.device, &screen)?;
+                }
+            }
+        }
         Ok(())
     }
 
-    pub fn shutdown(&self) -> Result<(), Box<dyn Error>> {
+    pub fn shutdown(&mut self) -> Result<(), Box<dyn Error>> {
+        self.pages = None;
+        let restored = match self.saved_screen_info.take() {
+            Some(screen) => {
+                // back on the page shown before setup, so capture reads that one
+                self.fb.var_screen_info.yoffset = screen.yoffset;
+                framebuffer::Framebuffer::put_var_screeninfo(&self.fb.device, &screen).map(|_| ())
+            }
+            None => Ok(()),
+        };
+        // get back to text mode even if the screen info couldn't be restored
         framebuffer::Framebuffer::set_kd_mode(framebuffer::KdMode::Text)?;
+        restored?;
         Ok(())
     }
 
-    pub fn width(&self) -> usize {
-        return self.fb.var_screen_info.xres as usize;
-    }
-
-    pub fn height(&self) -> usize {
-        return self.fb.var_screen_info.yres as usize;
+    pub fn write_frame(&mut self, pixels: &[u32]) {
+        let (width, height) = (self.width(), self.height());
+        let line_length = self.line_length();
+        let format = self.format;
+        match self.pages.as_mut() {
+            Some(pages) => {
+                pages.write_frame(&mut self.fb.frame, width, height, |dst| {
+                    copy_frame(format, pixels, width, height, line_length, dst)
+                });
+                self.flip();
+            }
+            None => copy_frame(
+                format,
+                pixels,
+                width,
+                height,
+                line_length,
+                self.front_buffer(),
+            ),
+        }
     }
 
     pub fn line_length(&self) -> usize {
-        return self.fb.fix_screen_info.line_length as usize;
+        self.fb.fix_screen_info.line_length as usize
     }
 
     pub fn bytes_per_pixel(&self) -> usize {
-        return (self.fb.var_screen_info.bits_per_pixel / 8) as usize;
+        self.format.bytes_per_pixel()
     }
 
-    pub fn write_frame(&mut self, pixels: &[u32]) {
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// What's on the screen now, whatever drew it.
+    pub fn capture(&self) -> Canvas {
+        let (width, height) = (self.width(), self.height());
+        let line_length = self.line_length();
+        let start = self.fb.var_screen_info.yoffset as usize * line_length;
+        let shown = self.fb.frame.get(start..).unwrap_or(&[]);
+        let pixels = self.format.read_frame(shown, width, height, line_length);
+        Canvas::new(width, height, &pixels)
+    }
+
+    /// Draw to an offscreen page and flip it onto the screen once complete,
+    /// so frames never tear. Takes effect on `setup`, staying with a single
+    /// buffer if the driver can't give a second page or pan between them.
+    /// `Config::set_double_buffering` does the same for a config's display.
+    pub fn set_double_buffering(&mut self, enabled: bool) {
+        self.double_buffering = enabled;
+    }
+
+    /// Whether frames are being flipped rather than written to the screen.
+    pub fn is_double_buffered(&self) -> bool {
+        self.pages.is_some()
+    }
+
+    // ask for a virtual screen two pages tall and map all of it
+    fn enable_page_flipping(&mut self) -> Result<(), Box<dyn Error>> {
+        let device = &self.fb.device;
+        let mut screen = framebuffer::Framebuffer::get_var_screeninfo(device)?;
+        screen.yres_virtual = screen.yres * 2;
+        screen.yoffset = 0;
+        framebuffer::Framebuffer::put_var_screeninfo(device, &screen)?;
+        let screen = framebuffer::Framebuffer::get_var_screeninfo(device)?;
+        let fixed = framebuffer::Framebuffer::get_fix_screeninfo(device)?;
+        let page_len = (fixed.line_length * screen.yres) as usize;
+        if screen.yres_virtual < screen.yres * 2
+            || (fixed.smem_len as usize) < page_len * 2
+            || fixed.ypanstep == 0
+        {
+            return Err("driver can't flip between two pages".into());
+        }
+        self.fb.frame = unsafe { MmapOptions::new().len(page_len * 2).map_mut(device)? };
+        self.fb.var_screen_info = screen;
+        self.fb.fix_screen_info = fixed;
+        Ok(())
+    }
+
+    // show the back page and start drawing to the other one
+    fn flip(&mut self) {
+        let pages = match self.pages.as_mut() {
+            Some(pages) => pages,
+            None => return,
+        };
+        let device = self.fb.device.as_raw_fd();
+        let mut screen = self.fb.var_screen_info.clone();
+        screen.yoffset = pages.back_page() as u32 * screen.yres;
+        if unsafe { libc::ioctl(device, FBIOPAN_DISPLAY as _, &screen) } == -1 {
+            // stop flipping, carrying the frame over to the page that's shown
+            pages.collapse(&mut self.fb.frame);
+            self.pages = None;
+            return;
+        }
+        self.fb.var_screen_info.yoffset = screen.yoffset;
+        if self.vsync {
+            // wait for the pan to happen before drawing over the old page
+            let mut crtc: u32 = 0;
+            self.vsync = unsafe { libc::ioctl(device, FBIO_WAITFORVSYNC as _, &mut crtc) } != -1;
+        }
+        pages.flipped();
+    }
+
+    // the page on screen, drawn to straight away without flipping
+    fn front_buffer(&mut self) -> &mut [u8] {
+        let start = self.fb.var_screen_info.yoffset as usize * self.line_length();
+        let end = usize::min(
+            start + self.line_length() * self.height(),
+            self.fb.frame.len(),
+        );
+        &mut self.fb.frame[start.min(end)..end]
+    }
+}
+
+fn copy_frame(
+    format: PixelFormat,
+    pixels: &[u32],
+    width: usize,
+    height: usize,
+    line_length: usize,
+    dst: &mut [u8],
+) {
+    if format == PixelFormat::XRGB8888 && line_length == width * 4 {
+        // canvas pixels are already laid out like the screen, copy them straight across
         let v_bytes: &[u8] = unsafe {
-            std::slice::from_raw_parts(
-                pixels.as_ptr() as *const u8,
-                pixels.len() * std::mem::size_of::<i32>(),
-            )
+            std::slice::from_raw_parts(pixels.as_ptr() as *const u8, std::mem::size_of_val(pixels))
         };
-        self.fb.write_frame(v_bytes);
+        let len = usize::min(v_bytes.len(), dst.len());
+        dst[..len].copy_from_slice(&v_bytes[..len]);
+        return;
+    }
+    format.write_frame(pixels, width, height, line_length, dst);
+}
+
+fn copy_region(
+    format: PixelFormat,
+    pixels: &[u32],
+    width: usize,
+    rect: Rect,
+    line_length: usize,
+    dst: &mut [u8],
+) {
+    if format == PixelFormat::XRGB8888 {
+        for y in rect.y..rect.bottom() {
+            let src = &pixels[y * width + rect.x..y * width + rect.right()];
+            let src: &[u8] = unsafe {
+                std::slice::from_raw_parts(src.as_ptr() as *const u8, std::mem::size_of_val(src))
+            };
+            let start = y * line_length + rect.x * 4;
+            if let Some(dst) = dst.get_mut(start..start + src.len()) {
+                dst.copy_from_slice(src);
+            }
+        }
+        return;
+    }
+    format.write_region(pixels, width, rect, line_length, dst);
+}
+
+impl Display for Framebuffer {
+    fn width(&self) -> usize {
+        Framebuffer::width(self)
+    }
+
+    fn height(&self) -> usize {
+        Framebuffer::height(self)
+    }
+
+    fn setup(&mut self) -> Result<(), Box<dyn Error>> {
+        Framebuffer::setup(self)
+    }
+
+    fn shutdown(&mut self) -> Result<(), Box<dyn Error>> {
+        Framebuffer::shutdown(self)
+    }
+
+    fn write_frame(&mut self, pixels: &[u32]) {
+        Framebuffer::write_frame(self, pixels)
+    }
+
+    fn write_region(&mut self, pixels: &[u32], rect: Rect) {
+        let width = self.width();
+        let line_length = self.line_length();
+        let format = self.format;
+        match self.pages.as_mut() {
+            // the canvas still has whatever went to the other page last flip
+            Some(pages) => pages.write_region(&mut self.fb.frame, rect, |dst, rect| {
+                copy_region(format, pixels, width, rect, line_length, dst)
+            }),
+            None => copy_region(
+                format,
+                pixels,
+                width,
+                rect,
+                line_length,
+                self.front_buffer(),
+            ),
+        }
+    }
+
+    fn present(&mut self) {
+        if self.pages.as_ref().is_some_and(PageFlip::needs_flip) {
+            self.flip();
+        }
+    }
+
+    fn set_double_buffering(&mut self, enabled: bool) {
+        Framebuffer::set_double_buffering(self, enabled)
     }
 }
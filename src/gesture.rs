@@ -1,11 +1,25 @@
 use crate::point::*;
 use crate::swipe::*;
+use crate::touch::*;
+use std::f32::consts::PI;
 use std::ops::Div;
 
 #[derive(Clone, Debug)]
 pub enum Gesture {
     Tap(Point),
     Drag(Point, Point),
+    // scale is the current finger distance over the distance when both fingers landed
+    Pinch {
+        center: Point,
+        scale: f32,
+        finished: bool,
+    },
+    // angle is in radians, clockwise on screen, since both fingers landed
+    Rotate {
+        center: Point,
+        angle: f32,
+        finished: bool,
+    },
 }
 
 impl Swipe {
@@ -49,3 +63,165 @@ impl Swipe {
         ))
     }
 }
+
+/// Follows the first two fingers of `Event::Touch` events to recognize
+/// pinches and rotations.
+#[derive(Clone, Debug, Default)]
+pub struct MultiTouchGesture {
+    fingers: Vec<Touch>,
+    start: Option<(Point, Point)>,
+    finished: bool,
+}
+
+impl MultiTouchGesture {
+    pub fn new() -> Self {
+        MultiTouchGesture::default()
+    }
+
+    pub fn update(&mut self, touch: &Touch) {
+        if self.finished {
+            self.fingers.retain(|f| !f.swipe.finished);
+            self.start = None;
+            self.finished = false;
+        }
+        if let Some(finger) = self.fingers.iter_mut().find(|f| f.id == touch.id) {
+            *finger = touch.clone();
+        } else if self.fingers.len() < 2 && !touch.swipe.finished {
+            self.fingers.push(touch.clone());
+        } else {
+            return;
+        }
+        if self.fingers.len() == 2 {
+            if self.start.is_none() {
+                self.start = Some((
+                    self.fingers[0].swipe.last().clone(),
+                    self.fingers[1].swipe.last().clone(),
+                ));
+            }
+            if touch.swipe.finished {
+                self.finished = true;
+            }
+        } else if touch.swipe.finished {
+            self.fingers.retain(|f| !f.swipe.finished);
+        }
+    }
+
+    fn current(&self) -> Option<(&Point, &Point, &(Point, Point))> {
+        match (self.fingers.as_slice(), &self.start) {
+            ([a, b], Some(start)) => Some((a.swipe.last(), b.swipe.last(), start)),
+            _ => None,
+        }
+    }
+
+    fn center(a: &Point, b: &Point) -> Point {
+        Point {
+            time: Timeval::max(a.time, b.time),
+            x: (a.x + b.x).div(2),
+            y: (a.y + b.y).div(2),
+        }
+    }
+
+    pub fn pinch(&self) -> Option<Gesture> {
+        let (a, b, (start_a, start_b)) = self.current()?;
+        let distance =
+            |p: &Point, q: &Point| (((q.x - p.x).pow(2) + (q.y - p.y).pow(2)) as f32).sqrt();
+        let start_distance = distance(start_a, start_b);
+        if start_distance == 0.0 {
+            return None;
+        }
+        Some(Gesture::Pinch {
+            center: MultiTouchGesture::center(a, b),
+            scale: distance(a, b) / start_distance,
+            finished: self.finished,
+        })
+    }
+
+    pub fn rotate(&self) -> Option<Gesture> {
+        let (a, b, (start_a, start_b)) = self.current()?;
+        let heading = |p: &Point, q: &Point| ((q.y - p.y) as f32).atan2((q.x - p.x) as f32);
+        let mut angle = heading(a, b) - heading(start_a, start_b);
+        if angle > PI {
+            angle -= 2.0 * PI;
+        } else if angle <= -PI {
+            angle += 2.0 * PI;
+        }
+        Some(Gesture::Rotate {
+            center: MultiTouchGesture::center(a, b),
+            angle,
+            finished: self.finished,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: usize, ms: isize, x: isize, y: isize) -> Touch {
+        Touch {
+            id,
+            swipe: Swipe::new(Point {
+                time: Timeval(0, ms * 1000),
+                x,
+                y,
+            }),
+        }
+    }
+
+    fn moved(mut t: Touch, ms: isize, x: isize, y: isize, finished: bool) -> Touch {
+        t.swipe.push(Point {
+            time: Timeval(0, ms * 1000),
+            x,
+            y,
+        });
+        t.swipe.finished = finished;
+        t
+    }
+
+    #[test]
+    fn pinch_and_rotate_follow_two_fingers() {
+        let mut g = MultiTouchGesture::new();
+        let a = touch(0, 0, 100, 100);
+        let b = touch(1, 0, 200, 100);
+        g.update(&a);
+        assert!(g.pinch().is_none());
+        g.update(&b);
+        match g.pinch() {
+            Some(Gesture::Pinch {
+                scale, finished, ..
+            }) => {
+                assert!((scale - 1.0).abs() < 1e-6);
+                assert!(!finished);
+            }
+            other => panic!("expected pinch, got {:?}", other),
+        }
+
+        // spread the fingers apart and turn them a quarter turn
+        let a = moved(a, 1, 150, 0, false);
+        let b = moved(b, 1, 150, 200, false);
+        g.update(&a);
+        g.update(&b);
+        match g.pinch() {
+            Some(Gesture::Pinch { center, scale, .. }) => {
+                assert!((scale - 2.0).abs() < 1e-6);
+                assert_eq!((center.x, center.y), (150, 100));
+            }
+            other => panic!("expected pinch, got {:?}", other),
+        }
+        match g.rotate() {
+            Some(Gesture::Rotate { angle, .. }) => assert!((angle - PI / 2.0).abs() < 1e-6),
+            other => panic!("expected rotate, got {:?}", other),
+        }
+
+        let b = moved(b, 2, 150, 200, true);
+        g.update(&b);
+        match g.rotate() {
+            Some(Gesture::Rotate { finished, .. }) => assert!(finished),
+            other => panic!("expected rotate, got {:?}", other),
+        }
+
+        // the remaining finger alone is not a gesture
+        g.update(&moved(a, 3, 160, 0, false));
+        assert!(g.pinch().is_none());
+    }
+}
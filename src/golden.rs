@@ -0,0 +1,154 @@
+use crate::canvas::Canvas;
+use crate::util::load_image;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Set to anything to have `Golden::new` make checkers that overwrite the
+/// stored images with what was rendered instead of comparing against them.
+pub const UPDATE_VAR: &str = "CONIFER_UPDATE_GOLDEN";
+
+/// Compares rendered canvases against PNGs stored in `dir`, for catching
+/// drawing regressions in tests.
+#[derive(Debug, Clone)]
+pub struct Golden {
+    pub dir: PathBuf,
+    // how far each channel may stray before a pixel counts as different
+    pub tolerance: u8,
+    // save what's drawn as the new golden images rather than checking it
+    pub update: bool,
+}
+
+impl Golden {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Golden {
+            dir: dir.as_ref().to_path_buf(),
+            tolerance: 0,
+            update: std::env::var_os(UPDATE_VAR).is_some(),
+        }
+    }
+
+    /// Check `canvas` matches `<dir>/<name>.png`. On a mismatch the rendered
+    /// image is written beside it as `<name>.actual.png`, and the differing
+    /// pixels shown in red over a faded copy of the golden as `<name>.diff.png`.
+    pub fn check(&self, name: &str, canvas: &Canvas) -> Result<(), Box<dyn Error>> {
+        let path = self.dir.join(format!("{}.png", name));
+        if self.update {
+            std::fs::create_dir_all(&self.dir)?;
+            return canvas.save(&path);
+        }
+        if !path.exists() {
+            return Err(format!(
+                "no golden image at {}, run with {} set to create it",
+                path.display(),
+                UPDATE_VAR
+            )
+            .into());
+        }
+        let golden = load_image(&path)?;
+        let actual = self.dir.join(format!("{}.actual.png", name));
+        if golden.width != canvas.width || golden.height != canvas.height {
+            canvas.save(&actual)?;
+            return Err(format!(
+                "{} is {}x{} but {}x{} was drawn, see {}",
+                path.display(),
+                golden.width,
+                golden.height,
+                canvas.width,
+                canvas.height,
+                actual.display()
+            )
+            .into());
+        }
+        let (diff, count) = self.diff(&golden, canvas);
+        let diff_path = self.dir.join(format!("{}.diff.png", name));
+        if count == 0 {
+            // clear out what an earlier failure left behind
+            let _ = std::fs::remove_file(&actual);
+            let _ = std::fs::remove_file(&diff_path);
+            return Ok(());
+        }
+        canvas.save(&actual)?;
+        diff.save(&diff_path)?;
+        Err(format!(
+            "{} pixels differ from {} by more than {}, see {}",
+            count,
+            path.display(),
+            self.tolerance,
+            diff_path.display()
+        )
+        .into())
+    }
+
+    // the golden faded towards white with differing pixels in red, and how many there are
+    fn diff(&self, golden: &Canvas, canvas: &Canvas) -> (Canvas, usize) {
+        let mut count = 0;
+        let pixels: Vec<u32> = golden
+            .pixels
+            .iter()
+            .zip(canvas.pixels.iter())
+            .map(|(&expected, &drawn)| {
+                let differs = [24, 16, 8, 0].iter().any(|shift| {
+                    let a = (expected >> shift) as u8;
+                    let b = (drawn >> shift) as u8;
+                    a.abs_diff(b) > self.tolerance
+                });
+                if differs {
+                    count += 1;
+                    return 0xffff_0000;
+                }
+                let faded = |shift: u32| (((expected >> shift) & 0xff) + 3 * 255) / 4;
+                0xff00_0000 | faded(16) << 16 | faded(8) << 8 | faded(0)
+            })
+            .collect();
+        (Canvas::new(golden.width, golden.height, &pixels), count)
+    }
+}
+
+/// Fail the test unless `canvas` matches the crate's golden image `name`.
+#[cfg(test)]
+pub(crate) fn assert_golden(name: &str, canvas: &Canvas) {
+    let mut golden = Golden::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden"));
+    // leave room for floating point differences between platforms
+    golden.tolerance = 2;
+    if let Err(err) = golden.check(name, canvas) {
+        panic!("{}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_and_draws_differences() {
+        let dir = std::env::temp_dir().join(format!("conifer-golden-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let golden = Golden {
+            dir: dir.clone(),
+            tolerance: 4,
+            update: false,
+        };
+        let canvas = Canvas::new(2, 1, &[0xff00_0000, 0xff80_8080]);
+        canvas.save(dir.join("grey.png")).unwrap();
+        assert!(golden.check("missing", &canvas).is_err());
+
+        // within tolerance
+        let close = Canvas::new(2, 1, &[0xff00_0004, 0xff7c_8080]);
+        golden.check("grey", &close).unwrap();
+        assert!(!dir.join("grey.diff.png").exists());
+
+        let off = Canvas::new(2, 1, &[0xff00_0000, 0xff80_8085]);
+        let err = golden.check("grey", &off).unwrap_err();
+        assert!(err.to_string().starts_with("1 pixels differ"));
+        let diff = load_image(dir.join("grey.diff.png")).unwrap();
+        assert_eq!(diff.pixels, vec![0xffbf_bfbf, 0xffff_0000]);
+        assert_eq!(
+            load_image(dir.join("grey.actual.png")).unwrap().pixels,
+            off.pixels
+        );
+
+        let wrong_size = Canvas::new(1, 1, &[0xff00_0000]);
+        assert!(golden.check("grey", &wrong_size).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+use crate::canvas::flatten_cubic;
+use crate::point::Timeval;
+use crate::swipe::Swipe;
+
+/// A point along a smoothed stroke and how wide the stroke is there.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InkPoint {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+}
+
+/// Turns swipes into smooth strokes that thin out as the finger speeds up,
+/// like a pen pressed less firmly.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ink {
+    // width while the finger is still
+    pub max_width: f32,
+    // width at max_speed and beyond
+    pub min_width: f32,
+    // pixels per second
+    pub max_speed: f32,
+}
+
+fn seconds(t: Timeval) -> f32 {
+    t.0 as f32 + t.1 as f32 / 1_000_000.0
+}
+
+impl Ink {
+    pub fn new(min_width: f32, max_width: f32) -> Self {
+        Ink {
+            max_width,
+            min_width,
+            max_speed: 2000.0,
+        }
+    }
+
+    // the width for each point of the swipe, from how fast it was reached
+    fn widths(&self, swipe: &Swipe) -> Vec<f32> {
+        let points = &swipe.points;
+        let mut widths = Vec::with_capacity(points.len());
+        let mut width = self.max_width;
+        for (i, p) in points.iter().enumerate() {
+            // the first point takes the speed of the first movement
+            let (a, b) = match i {
+                0 if points.len() > 1 => (&points[0], &points[1]),
+                0 => (p, p),
+                _ => (&points[i - 1], p),
+            };
+            let dt = seconds(b.time) - seconds(a.time);
+            if dt > 0.0 {
+                let distance = (((b.x - a.x).pow(2) + (b.y - a.y).pow(2)) as f32).sqrt();
+                let slowness = 1.0 - (distance / dt / self.max_speed).min(1.0);
+                let target = self.min_width + (self.max_width - self.min_width) * slowness;
+                // ease towards the new width so the stroke doesn't jump
+                width = if i == 0 {
+                    target
+                } else {
+                    width * 0.6 + target * 0.4
+                };
+            }
+            widths.push(width);
+        }
+        widths
+    }
+
+    /// A Catmull-Rom spline through the points of `swipe`, broken into short
+    /// straight pieces, ready for `Canvas::draw_ink`.
+    pub fn path(&self, swipe: &Swipe) -> Vec<InkPoint> {
+        let points: Vec<(f32, f32)> = swipe
+            .points
+            .iter()
+            .map(|p| (p.x as f32, p.y as f32))
+            .collect();
+        let widths = self.widths(swipe);
+        let mut path = vec![InkPoint {
+            x: points[0].0,
+            y: points[0].1,
+            width: widths[0],
+        }];
+        let last = points.len() - 1;
+        for i in 0..last {
+            // the ends repeat so the spline still reaches them
+            let p0 = points[i.saturating_sub(1)];
+            let (p1, p2) = (points[i], points[i + 1]);
+            let p3 = points[(i + 2).min(last)];
+            let c0 = (p1.0 + (p2.0 - p0.0) / 6.0, p1.1 + (p2.1 - p0.1) / 6.0);
+            let c1 = (p2.0 - (p3.0 - p1.0) / 6.0, p2.1 - (p3.1 - p1.1) / 6.0);
+            let pieces = flatten_cubic(p1, c0, c1, p2);
+            let n = pieces.len() - 1;
+            for (j, &(x, y)) in pieces.iter().enumerate().skip(1) {
+                let t = j as f32 / n as f32;
+                path.push(InkPoint {
+                    x,
+                    y,
+                    width: widths[i] + (widths[i + 1] - widths[i]) * t,
+                });
+            }
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Point;
+
+    fn swipe(points: &[(isize, isize, isize)]) -> Swipe {
+        let point = |&(x, y, ms): &(isize, isize, isize)| Point {
+            time: Timeval(0, ms * 1000),
+            x,
+            y,
+        };
+        let mut swipe = Swipe::new(point(&points[0]));
+        for p in &points[1..] {
+            swipe.push(point(p));
+        }
+        swipe
+    }
+
+    #[test]
+    fn path_passes_through_points() {
+        let s = swipe(&[(0, 0, 0), (100, 0, 100), (100, 100, 200)]);
+        let path = Ink::new(2.0, 8.0).path(&s);
+        for p in &s.points {
+            assert!(path.iter().any(|i| i.x == p.x as f32 && i.y == p.y as f32));
+        }
+        assert!(path.len() > s.points.len());
+        // the corner is rounded off rather than cut through
+        assert!(path.iter().any(|i| i.x > 100.0));
+    }
+
+    #[test]
+    fn faster_is_thinner() {
+        let ink = Ink::new(2.0, 8.0);
+        let slow = ink.path(&swipe(&[(0, 0, 0), (10, 0, 100), (20, 0, 200)]));
+        let fast = ink.path(&swipe(&[(0, 0, 0), (300, 0, 100), (600, 0, 200)]));
+        let last = |path: &[InkPoint]| path.last().unwrap().width;
+        assert!(last(&slow) > 7.0);
+        assert!(last(&fast) < 3.0);
+        // a tap is a dot at full width
+        let tap = ink.path(&swipe(&[(5, 5, 0)]));
+        assert_eq!(
+            tap,
+            vec![InkPoint {
+                x: 5.0,
+                y: 5.0,
+                width: 8.0
+            }]
+        );
+    }
+}
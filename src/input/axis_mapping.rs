@@ -0,0 +1,85 @@
+use crate::input::Orientation;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// Scales raw absolute axis values from a digitizer into screen pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AxisMapping {
+    pub input_min_width: f32,
+    pub input_min_height: f32,
+    pub input_max_width: f32,
+    pub input_max_height: f32,
+    pub screen_width: usize,
+    pub screen_height: usize,
+    pub orientation: Orientation,
+}
+
+impl AxisMapping {
+    /// Map a raw value on a digitizer axis to the screen axis it lands on and
+    /// its position in pixels along that axis.
+    pub fn map(&self, axis: Axis, raw: isize) -> (Axis, isize) {
+        let (min, max) = match axis {
+            Axis::X => (self.input_min_width, self.input_max_width),
+            Axis::Y => (self.input_min_height, self.input_max_height),
+        };
+        let screen_axis = match (axis, self.orientation.swap_axes) {
+            (Axis::X, false) | (Axis::Y, true) => Axis::X,
+            (Axis::Y, false) | (Axis::X, true) => Axis::Y,
+        };
+        let (size, invert) = match screen_axis {
+            Axis::X => (self.screen_width, self.orientation.invert_x),
+            Axis::Y => (self.screen_height, self.orientation.invert_y),
+        };
+        if max <= min || size == 0 {
+            // nothing sensible to scale by, pass the digitizer value through
+            return (screen_axis, raw);
+        }
+        let t = ((raw as f32 - min) / (max - min)).clamp(0.0, 1.0);
+        let t = if invert { 1.0 - t } else { t };
+        let pixel = (t * (size - 1) as f32).round() as isize;
+        (screen_axis, pixel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(orientation: Orientation) -> AxisMapping {
+        AxisMapping {
+            input_min_width: 0.0,
+            input_min_height: 0.0,
+            input_max_width: 4095.0,
+            input_max_height: 2047.0,
+            screen_width: 720,
+            screen_height: 1440,
+            orientation,
+        }
+    }
+
+    #[test]
+    fn scales_to_screen() {
+        let m = mapping(Orientation::default());
+        assert_eq!(m.map(Axis::X, 0), (Axis::X, 0));
+        assert_eq!(m.map(Axis::X, 4095), (Axis::X, 719));
+        assert_eq!(m.map(Axis::Y, 2047), (Axis::Y, 1439));
+        // out of range values are clamped onto the screen
+        assert_eq!(m.map(Axis::Y, 5000), (Axis::Y, 1439));
+    }
+
+    #[test]
+    fn swaps_and_inverts() {
+        let m = mapping(Orientation {
+            swap_axes: true,
+            invert_x: true,
+            invert_y: false,
+        });
+        assert_eq!(m.map(Axis::X, 0), (Axis::Y, 0));
+        assert_eq!(m.map(Axis::Y, 0), (Axis::X, 719));
+        assert_eq!(m.map(Axis::Y, 2047), (Axis::X, 0));
+    }
+}
@@ -1,15 +1,28 @@
+use crate::input::axis_mapping::{Axis, AxisMapping};
+use crate::input::Input;
 use crate::input::InputEvent;
+use crate::input::Orientation;
 use crate::point::Timeval;
 use evdev::{Device, ABSOLUTE};
 use std::error::Error;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 
+const EV_SYN: u16 = 0;
 const EV_KEY: u16 = 1;
 const EV_ABS: u16 = 3;
+const SYN_REPORT: u16 = 0;
 const ABS_X: u16 = 0;
 const ABS_Y: u16 = 1;
+const ABS_MT_SLOT: u16 = 0x2f;
+const ABS_MT_POSITION_X: u16 = 0x35;
+const ABS_MT_POSITION_Y: u16 = 0x36;
+const ABS_MT_TRACKING_ID: u16 = 0x39;
 const BTN_TOUCH: u16 = 330;
 
+// how often to check whether we've been asked to stop while no input arrives
+const POLL_TIMEOUT_MS: libc::c_int = 100;
+
 #[derive(Debug)]
 pub struct EventInput {
     input_device: Device,
@@ -17,6 +30,9 @@ pub struct EventInput {
     pub input_min_height: f32,
     pub input_max_width: f32,
     pub input_max_height: f32,
+    pub screen_width: usize,
+    pub screen_height: usize,
+    pub orientation: Orientation,
 }
 
 impl EventInput {
@@ -35,6 +51,9 @@ impl EventInput {
             input_min_height,
             input_max_width,
             input_max_height,
+            screen_width: 0,
+            screen_height: 0,
+            orientation: Orientation::default(),
         })
     }
 
@@ -61,6 +80,9 @@ impl EventInput {
                         input_min_height: y_abs_val.minimum as f32,
                         input_max_width: x_abs_val.maximum as f32,
                         input_max_height: y_abs_val.maximum as f32,
+                        screen_width: 0,
+                        screen_height: 0,
+                        orientation: Orientation::default(),
                     });
                 }
             }
@@ -68,24 +90,79 @@ impl EventInput {
         Err("Could not find a valid input device")
     }
 
-    pub fn on_event(
+    // multitouch positions share the single touch axis ranges, which is what
+    // touchscreen drivers report in practice
+    pub fn mapping(&self) -> AxisMapping {
+        AxisMapping {
+            input_min_width: self.input_min_width,
+            input_min_height: self.input_min_height,
+            input_max_width: self.input_max_width,
+            input_max_height: self.input_max_height,
+            screen_width: self.screen_width,
+            screen_height: self.screen_height,
+            orientation: self.orientation,
+        }
+    }
+}
+
+impl Input for EventInput {
+    fn on_event(
         &mut self,
-        mut f: impl FnMut(InputEvent) -> Result<(), Box<dyn Error>>,
+        running: &AtomicBool,
+        f: &mut dyn FnMut(InputEvent) -> Result<(), Box<dyn Error>>,
     ) -> Result<(), Box<dyn Error>> {
-        loop {
+        let mapping = self.mapping();
+        let single = |axis, value, time| match mapping.map(axis, value as isize) {
+            (Axis::X, x) => InputEvent::PartialX(x, Timeval::from_timeval(time)),
+            (Axis::Y, y) => InputEvent::PartialY(y, Timeval::from_timeval(time)),
+        };
+        let multi = |axis, value, time| match mapping.map(axis, value as isize) {
+            (Axis::X, x) => InputEvent::MultiX(x, Timeval::from_timeval(time)),
+            (Axis::Y, y) => InputEvent::MultiY(y, Timeval::from_timeval(time)),
+        };
+        while running.load(Ordering::SeqCst) {
+            // sleep until the device has events rather than spinning on it
+            let mut fds = libc::pollfd {
+                fd: self.input_device.fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut fds, 1, POLL_TIMEOUT_MS) } < 0 {
+                let err = std::io::Error::last_os_error();
+                if err.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err.into());
+            }
+            if fds.revents == 0 {
+                continue;
+            }
             for ev in self.input_device.events_no_sync()? {
                 let e = match (ev._type, ev.code, ev.value, ev.time) {
-                    (EV_ABS, ABS_X, x, time) => {
-                        InputEvent::PartialX(x as isize, Timeval::from_timeval(time))
-                    }
-                    (EV_ABS, ABS_Y, y, time) => {
-                        InputEvent::PartialY(y as isize, Timeval::from_timeval(time))
+                    (EV_ABS, ABS_X, x, time) => single(Axis::X, x, time),
+                    (EV_ABS, ABS_Y, y, time) => single(Axis::Y, y, time),
+                    (EV_ABS, ABS_MT_SLOT, slot, _) => InputEvent::MultiSlot(slot as usize),
+                    (EV_ABS, ABS_MT_TRACKING_ID, id, time) => {
+                        InputEvent::MultiTrackingId(id as isize, Timeval::from_timeval(time))
                     }
+                    (EV_ABS, ABS_MT_POSITION_X, x, time) => multi(Axis::X, x, time),
+                    (EV_ABS, ABS_MT_POSITION_Y, y, time) => multi(Axis::Y, y, time),
+                    (EV_SYN, SYN_REPORT, _, time) => InputEvent::Sync(Timeval::from_timeval(time)),
                     (EV_KEY, BTN_TOUCH, 0, _) => InputEvent::ButtonDown(0),
                     _ => InputEvent::Unknown,
                 };
                 f(e)?;
             }
         }
+        Ok(())
+    }
+
+    fn set_screen_size(&mut self, width: usize, height: usize) {
+        self.screen_width = width;
+        self.screen_height = height;
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
     }
 }
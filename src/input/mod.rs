@@ -1,10 +1,46 @@
 use crate::point::Timeval;
+use std::error::Error;
+use std::fmt::Debug;
+use std::sync::atomic::AtomicBool;
 
+pub mod axis_mapping;
 pub mod event_input;
+pub mod recorded_input;
+pub mod scripted_input;
 
+#[derive(Clone, Debug)]
 pub enum InputEvent {
     PartialX(isize, Timeval),
     PartialY(isize, Timeval),
     ButtonDown(usize),
+    MultiSlot(usize),
+    MultiTrackingId(isize, Timeval),
+    MultiX(isize, Timeval),
+    MultiY(isize, Timeval),
+    Sync(Timeval),
     Unknown,
 }
+
+/// How a touch panel is mounted relative to the screen. Axes are swapped
+/// first, then the resulting screen axes are inverted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Orientation {
+    pub swap_axes: bool,
+    pub invert_x: bool,
+    pub invert_y: bool,
+}
+
+pub trait Input: Debug + Send {
+    /// Deliver events to `f` until the source runs out, `f` fails, or
+    /// `running` is cleared.
+    fn on_event(
+        &mut self,
+        running: &AtomicBool,
+        f: &mut dyn FnMut(InputEvent) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// Called with the display size so raw coordinates can be scaled to it.
+    fn set_screen_size(&mut self, _width: usize, _height: usize) {}
+
+    fn set_orientation(&mut self, _orientation: Orientation) {}
+}
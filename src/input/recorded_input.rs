@@ -0,0 +1,304 @@
+use crate::input::Input;
+use crate::input::InputEvent;
+use crate::input::Orientation;
+use crate::point::Timeval;
+use std::error::Error;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+// the longest a replay sleeps before checking whether it's been stopped
+const MAX_SLEEP: Duration = Duration::from_millis(100);
+
+/// Passes events through from another input source, writing each one to a
+/// trace file that `ReplayInput` can play back.
+pub struct RecordingInput {
+    input: Box<dyn Input>,
+    out: BufWriter<File>,
+    // events like ButtonDown carry no time, so they're given the last one seen
+    last_time: Timeval,
+}
+
+impl Debug for RecordingInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordingInput")
+            .field("input", &self.input)
+            .field("last_time", &self.last_time)
+            .finish()
+    }
+}
+
+impl RecordingInput {
+    pub fn new<P: AsRef<Path>>(input: Box<dyn Input>, path: P) -> Result<Self, Box<dyn Error>> {
+        Ok(RecordingInput::from_file(input, File::create(path)?))
+    }
+
+    pub fn from_file(input: Box<dyn Input>, trace: File) -> Self {
+        RecordingInput {
+            input,
+            out: BufWriter::new(trace),
+            last_time: Timeval(0, 0),
+        }
+    }
+}
+
+impl Input for RecordingInput {
+    fn on_event(
+        &mut self,
+        running: &AtomicBool,
+        f: &mut dyn FnMut(InputEvent) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let out = &mut self.out;
+        let last_time = &mut self.last_time;
+        let result = self.input.on_event(running, &mut |ev| {
+            if let Some(time) = time_of(&ev) {
+                *last_time = time;
+            }
+            writeln!(out, "{}", format_event(*last_time, &ev))?;
+            if let InputEvent::Sync(_) | InputEvent::ButtonDown(_) = ev {
+                // keep the trace whole up to here in case we don't exit cleanly
+                out.flush()?;
+            }
+            f(ev)
+        });
+        self.out.flush()?;
+        result
+    }
+
+    fn set_screen_size(&mut self, width: usize, height: usize) {
+        self.input.set_screen_size(width, height);
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.input.set_orientation(orientation);
+    }
+}
+
+/// Plays back a trace written by `RecordingInput`, either with the gaps
+/// between events as they were recorded or as fast as possible. Recorded
+/// points are already mapped to the screen they were recorded on, but not
+/// yet calibrated.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayInput {
+    events: Vec<(Timeval, InputEvent)>,
+    pub real_time: bool,
+}
+
+impl ReplayInput {
+    pub fn new(events: Vec<(Timeval, InputEvent)>, real_time: bool) -> Self {
+        ReplayInput { events, real_time }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P, real_time: bool) -> Result<Self, Box<dyn Error>> {
+        ReplayInput::parse(&std::fs::read_to_string(path)?, real_time)
+    }
+
+    pub fn parse(trace: &str, real_time: bool) -> Result<Self, Box<dyn Error>> {
+        let mut events = vec![];
+        for (i, line) in trace.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event =
+                parse_event(line).ok_or_else(|| format!("bad input trace line {}", i + 1))?;
+            events.push(event);
+        }
+        Ok(ReplayInput::new(events, real_time))
+    }
+
+    pub fn events(&self) -> &[(Timeval, InputEvent)] {
+        &self.events
+    }
+}
+
+impl Input for ReplayInput {
+    fn on_event(
+        &mut self,
+        running: &AtomicBool,
+        f: &mut dyn FnMut(InputEvent) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut previous: Option<Timeval> = None;
+        for (time, ev) in self.events.drain(..) {
+            if self.real_time {
+                let gap = previous.map_or(0, |p| micros(time).saturating_sub(micros(p)));
+                let mut wait = Duration::from_micros(gap.max(0) as u64);
+                while !wait.is_zero() && running.load(Ordering::SeqCst) {
+                    let nap = wait.min(MAX_SLEEP);
+                    std::thread::sleep(nap);
+                    wait -= nap;
+                }
+                previous = Some(time);
+            }
+            if !running.load(Ordering::SeqCst) {
+                break;
+            }
+            f(ev)?;
+        }
+        Ok(())
+    }
+}
+
+// saturating, as a trace may hold any time at all
+fn micros(t: Timeval) -> isize {
+    t.0.saturating_mul(1_000_000).saturating_add(t.1)
+}
+
+fn time_of(ev: &InputEvent) -> Option<Timeval> {
+    match ev {
+        InputEvent::PartialX(_, t)
+        | InputEvent::PartialY(_, t)
+        | InputEvent::MultiTrackingId(_, t)
+        | InputEvent::MultiX(_, t)
+        | InputEvent::MultiY(_, t)
+        | InputEvent::Sync(t) => Some(*t),
+        _ => None,
+    }
+}
+
+// one line of a trace: seconds, microseconds, then the event and its value
+fn format_event(time: Timeval, ev: &InputEvent) -> String {
+    let event = match ev {
+        InputEvent::PartialX(x, _) => format!("x {}", x),
+        InputEvent::PartialY(y, _) => format!("y {}", y),
+        InputEvent::ButtonDown(b) => format!("button {}", b),
+        InputEvent::MultiSlot(s) => format!("slot {}", s),
+        InputEvent::MultiTrackingId(id, _) => format!("tracking {}", id),
+        InputEvent::MultiX(x, _) => format!("mx {}", x),
+        InputEvent::MultiY(y, _) => format!("my {}", y),
+        InputEvent::Sync(_) => "sync".to_string(),
+        InputEvent::Unknown => "unknown".to_string(),
+    };
+    format!("{} {} {}", time.0, time.1, event)
+}
+
+fn parse_event(line: &str) -> Option<(Timeval, InputEvent)> {
+    let mut parts = line.split_whitespace();
+    let time = Timeval(parts.next()?.parse().ok()?, parts.next()?.parse().ok()?);
+    let kind = parts.next()?;
+    let value = parts.next();
+    // buttons and slots parse as unsigned, so negative ones are rejected
+    fn parse<T: std::str::FromStr>(value: Option<&str>) -> Option<T> {
+        value?.parse().ok()
+    }
+    let ev = match kind {
+        "x" => InputEvent::PartialX(parse(value)?, time),
+        "y" => InputEvent::PartialY(parse(value)?, time),
+        "button" => InputEvent::ButtonDown(parse(value)?),
+        "slot" => InputEvent::MultiSlot(parse(value)?),
+        "tracking" => InputEvent::MultiTrackingId(parse(value)?, time),
+        "mx" => InputEvent::MultiX(parse(value)?, time),
+        "my" => InputEvent::MultiY(parse(value)?, time),
+        "sync" => InputEvent::Sync(time),
+        "unknown" => InputEvent::Unknown,
+        _ => return None,
+    };
+    Some((time, ev))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::scripted_input::ScriptedInput;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    fn record(input: ScriptedInput, path: &Path) -> Vec<String> {
+        let mut recording = RecordingInput::new(Box::new(input), path).unwrap();
+        let mut seen = vec![];
+        recording
+            .on_event(&AtomicBool::new(true), &mut |ev| {
+                seen.push(format!("{:?}", ev));
+                Ok(())
+            })
+            .unwrap();
+        seen
+    }
+
+    #[test]
+    fn records_and_replays() {
+        let path = std::env::temp_dir().join(format!("conifer-trace-{}", std::process::id()));
+        let mut input = ScriptedInput::swipe(&[(1, 2), (3, 4)]);
+        input.push(InputEvent::MultiSlot(1));
+        input.push(InputEvent::MultiTrackingId(-1, Timeval(1, 5)));
+        input.push(InputEvent::Sync(Timeval(1, 5)));
+        let seen = record(input, &path);
+
+        let trace = std::fs::read_to_string(&path).unwrap();
+        assert!(trace.starts_with("0 0 x 1\n0 0 y 2\n0 1000 x 3\n"));
+        // the button has no time of its own, so it gets the last one
+        assert!(trace.contains("0 1000 button 0\n"));
+
+        let mut replay = ReplayInput::load(&path, false).unwrap();
+        let mut replayed = vec![];
+        replay
+            .on_event(&AtomicBool::new(true), &mut |ev| {
+                replayed.push(format!("{:?}", ev));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(replayed, seen);
+        std::fs::remove_file(&path).unwrap();
+        assert!(ReplayInput::parse("0 0 x\n", false).is_err());
+        assert!(ReplayInput::parse("0 0 button -1\n", false).is_err());
+        assert!(ReplayInput::parse("0 0 slot -2\n", false).is_err());
+    }
+
+    #[test]
+    fn real_time_keeps_the_gaps() {
+        let trace = "5 990000 x 1\n6 20000 y 1\n6 20000 button 0\n";
+        let mut replay = ReplayInput::parse(trace, true).unwrap();
+        let start = Instant::now();
+        let mut count = 0;
+        replay
+            .on_event(&AtomicBool::new(true), &mut |_| {
+                count += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, 3);
+        assert!(start.elapsed() >= Duration::from_millis(30));
+
+        // stopping part way through a long wait cuts it short
+        let mut replay = ReplayInput::parse("0 0 x 1\n60 0 x 2\n", true).unwrap();
+        let running = Arc::new(AtomicBool::new(true));
+        let stopper = {
+            let running = running.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(150));
+                running.store(false, Ordering::SeqCst);
+            })
+        };
+        let start = Instant::now();
+        let mut count = 0;
+        replay
+            .on_event(&running, &mut |_| {
+                count += 1;
+                Ok(())
+            })
+            .unwrap();
+        stopper.join().unwrap();
+        assert_eq!(count, 1);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(150));
+        assert!(elapsed < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn huge_times_do_not_overflow() {
+        assert_eq!(micros(Timeval(isize::MAX, 5)), isize::MAX);
+        // time going backwards a long way is no wait at all
+        let trace = format!("{} 0 x 1\n{} 0 x 2\n", isize::MAX, isize::MIN);
+        let mut replay = ReplayInput::parse(&trace, true).unwrap();
+        let mut count = 0;
+        replay
+            .on_event(&AtomicBool::new(true), &mut |_| {
+                count += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+}
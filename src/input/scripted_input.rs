@@ -0,0 +1,55 @@
+use crate::input::Input;
+use crate::input::InputEvent;
+use crate::point::Timeval;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// An input source that replays a fixed list of events and then stops.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    events: Vec<InputEvent>,
+}
+
+impl ScriptedInput {
+    pub fn new(events: Vec<InputEvent>) -> Self {
+        ScriptedInput { events }
+    }
+
+    /// Script a single finger touching down at each point in turn and then
+    /// lifting off, one millisecond apart.
+    pub fn swipe(points: &[(isize, isize)]) -> Self {
+        let mut input = ScriptedInput::default();
+        input.push_swipe(points, Timeval(0, 0));
+        input
+    }
+
+    pub fn push_swipe(&mut self, points: &[(isize, isize)], start: Timeval) {
+        for (i, (x, y)) in points.iter().enumerate() {
+            let micros = start.1 + (i as isize) * 1000;
+            let time = Timeval(start.0 + micros / 1_000_000, micros % 1_000_000);
+            self.events.push(InputEvent::PartialX(*x, time));
+            self.events.push(InputEvent::PartialY(*y, time));
+        }
+        self.events.push(InputEvent::ButtonDown(0));
+    }
+
+    pub fn push(&mut self, event: InputEvent) {
+        self.events.push(event);
+    }
+}
+
+impl Input for ScriptedInput {
+    fn on_event(
+        &mut self,
+        running: &AtomicBool,
+        f: &mut dyn FnMut(InputEvent) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        for ev in self.events.drain(..) {
+            if !running.load(Ordering::SeqCst) {
+                break;
+            }
+            f(ev)?;
+        }
+        Ok(())
+    }
+}
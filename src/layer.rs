@@ -0,0 +1,357 @@
+use crate::blend::BlendMode;
+use crate::canvas::Canvas;
+use crate::rect::Rect;
+
+/// One named canvas in a `LayerStack`.
+#[derive(Debug)]
+pub struct Layer {
+    pub name: String,
+    pub canvas: Canvas,
+    // layers with higher z are drawn over those with lower z
+    pub z: i32,
+    pub visible: bool,
+    pub opacity: f32,
+    pub mode: BlendMode,
+    // where the layer's top left corner sits on the frame
+    pub x: isize,
+    pub y: isize,
+    id: usize,
+    dirty: bool,
+    // the part of the frame it covered when last composited
+    drawn: Option<Rect>,
+}
+
+/// Canvases drawn over each other into a frame, which is only recomposited
+/// where layers changed since the last frame, from the lowest that did.
+#[derive(Debug)]
+pub struct LayerStack {
+    width: usize,
+    height: usize,
+    layers: Vec<Layer>,
+    next_id: usize,
+    // the layers known not to have changed, by id, and their composite
+    base: Vec<usize>,
+    base_canvas: Option<Canvas>,
+    // whether the frame holds every layer, so changes can be drawn over it
+    composited: bool,
+}
+
+impl LayerStack {
+    /// An empty stack compositing frames of `width` by `height`.
+    pub fn new(width: usize, height: usize) -> Self {
+        LayerStack {
+            width,
+            height,
+            layers: vec![],
+            next_id: 0,
+            base: vec![],
+            base_canvas: None,
+            composited: false,
+        }
+    }
+
+    /// Add a transparent layer the size of the stack.
+    pub fn add(&mut self, name: &str, z: i32) -> &mut Layer {
+        let canvas = Canvas::new(self.width, self.height, &vec![0; self.width * self.height]);
+        self.add_canvas(name, z, canvas)
+    }
+
+    /// Add `canvas` as a layer, replacing any layer already called `name`.
+    pub fn add_canvas(&mut self, name: &str, z: i32, canvas: Canvas) -> &mut Layer {
+        self.remove(name);
+        self.layers.push(Layer {
+            name: name.to_string(),
+            canvas,
+            z,
+            visible: true,
+            opacity: 1.0,
+            mode: BlendMode::Normal,
+            x: 0,
+            y: 0,
+            id: self.next_id,
+            dirty: true,
+            drawn: None,
+        });
+        self.next_id += 1;
+        self.layers.last_mut().unwrap()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Layer> {
+        let i = self.layers.iter().position(|l| l.name == name)?;
+        // what was beneath it is still valid, but simplest to start over
+        self.clear_base();
+        self.composited = false;
+        Some(self.layers.remove(i))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|l| l.name == name)
+    }
+
+    /// The layer called `name`, marked as changed so it's recomposited.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Layer> {
+        let layer = self.layers.iter_mut().find(|l| l.name == name)?;
+        layer.dirty = true;
+        Some(layer)
+    }
+
+    /// The canvas of the layer called `name`, marked as changed.
+    pub fn canvas_mut(&mut self, name: &str) -> Option<&mut Canvas> {
+        self.get_mut(name).map(|l| &mut l.canvas)
+    }
+
+    pub fn mark_dirty(&mut self, name: &str) {
+        self.get_mut(name);
+    }
+
+    /// Layer names from the bottom of the stack to the top.
+    pub fn names(&self) -> Vec<&str> {
+        let mut layers: Vec<&Layer> = self.layers.iter().collect();
+        layers.sort_by_key(|l| l.z);
+        layers.iter().map(|l| l.name.as_str()).collect()
+    }
+
+    /// Draw every visible layer into `frame`, which should be the same canvas
+    /// each time as only the areas of layers that changed are drawn over it
+    /// and damaged. Returns whether anything was drawn.
+    pub fn composite(&mut self, frame: &mut Canvas) -> Result<bool, &'static str> {
+        if frame.width != self.width || frame.height != self.height {
+            return Err("frame is not the size of the layer stack");
+        }
+        // stable, so layers with the same z keep the order they were added in
+        self.layers.sort_by_key(|l| l.z);
+        let dirty = match self.layers.iter().position(|l| l.dirty) {
+            Some(dirty) => dirty,
+            None if self.composited => return Ok(false),
+            None => self.layers.len(),
+        };
+        let area = if self.composited {
+            // where changed layers were and are now
+            let (width, height) = (self.width, self.height);
+            let changed = self.layers.iter().filter(|l| l.dirty);
+            let area = changed
+                .flat_map(|l| l.drawn.into_iter().chain(l.bounds(width, height)))
+                .reduce(|a, b| a.union(&b));
+            match area {
+                Some(area) => area,
+                None => {
+                    self.layers.iter_mut().for_each(|l| l.dirty = false);
+                    return Ok(false);
+                }
+            }
+        } else {
+            Rect::new(0, 0, self.width, self.height)
+        };
+
+        // everything beneath the first changed layer is kept for next time
+        self.update_base(dirty)?;
+        let mut canvas = match self.base_canvas.as_ref() {
+            Some(base) => region(base, area),
+            None => Canvas::new(area.width, area.height, &vec![0; area.area()]),
+        };
+        let (x, y) = (area.x as isize, area.y as isize);
+        let (width, height) = (self.width, self.height);
+        for layer in &mut self.layers[self.base.len()..] {
+            if layer.visible {
+                let (lx, ly) = (layer.x - x, layer.y - y);
+                canvas.draw_canvas(&layer.canvas, lx, ly, layer.mode, layer.opacity)?;
+            }
+            layer.drawn = layer.bounds(width, height);
+            layer.dirty = false;
+        }
+        frame.draw_canvas(&canvas, x, y, BlendMode::Replace, 1.0)?;
+        self.composited = true;
+        Ok(true)
+    }
+
+    // make the base the composite of the first `len` layers, reusing as much
+    // of the one kept as is still the bottom of the stack in the same order
+    fn update_base(&mut self, len: usize) -> Result<(), &'static str> {
+        let reusable = self.base.len() <= len
+            && self
+                .layers
+                .iter()
+                .zip(&self.base)
+                .all(|(layer, id)| layer.id == *id);
+        if !reusable {
+            self.clear_base();
+        }
+        if self.base.len() == len {
+            return Ok(());
+        }
+        let (width, height) = (self.width, self.height);
+        let mut base = self
+            .base_canvas
+            .take()
+            .unwrap_or_else(|| Canvas::new(width, height, &vec![0; width * height]));
+        for layer in &self.layers[self.base.len()..len] {
+            if layer.visible {
+                base.draw_canvas(&layer.canvas, layer.x, layer.y, layer.mode, layer.opacity)?;
+            }
+            self.base.push(layer.id);
+        }
+        self.base_canvas = Some(base);
+        Ok(())
+    }
+
+    // forget the cached composite so the next frame is drawn from the bottom
+    fn clear_base(&mut self) {
+        self.base.clear();
+        self.base_canvas = None;
+    }
+}
+
+impl Layer {
+    // the part of a frame of `width` by `height` the layer covers, if shown
+    fn bounds(&self, width: usize, height: usize) -> Option<Rect> {
+        if !self.visible {
+            return None;
+        }
+        let (w, h) = (self.canvas.width, self.canvas.height);
+        Rect::clipped(self.x, self.y, w, h, width, height)
+    }
+}
+
+// a copy of `area` of `canvas`
+fn region(canvas: &Canvas, area: Rect) -> Canvas {
+    let mut pixels = Vec::with_capacity(area.area());
+    for row in area.y..area.bottom() {
+        let start = row * canvas.width + area.x;
+        pixels.extend_from_slice(&canvas.pixels[start..start + area.width]);
+    }
+    Canvas::new(area.width, area.height, &pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: u32 = 0xffff_0000;
+    const BLUE: u32 = 0xff00_00ff;
+
+    fn frame() -> Canvas {
+        Canvas::new(4, 1, &[0; 4])
+    }
+
+    #[test]
+    fn layers_stack_by_z() {
+        let mut stack = LayerStack::new(4, 1);
+        stack.add_canvas("hud", 10, Canvas::new(2, 1, &[BLUE, 0]));
+        stack.add_canvas("background", 0, Canvas::new(4, 1, &[RED; 4]));
+        stack.get_mut("hud").unwrap().x = 1;
+        assert_eq!(stack.names(), vec!["background", "hud"]);
+
+        let mut f = frame();
+        assert!(stack.composite(&mut f).unwrap());
+        assert_eq!(f.pixels, vec![RED, BLUE, RED, RED]);
+
+        // nothing changed, the frame is left alone
+        f.take_damage();
+        assert!(!stack.composite(&mut f).unwrap());
+        assert_eq!(f.pixels, vec![RED, BLUE, RED, RED]);
+        assert!(f.take_damage().is_empty());
+
+        stack.get_mut("hud").unwrap().visible = false;
+        stack.composite(&mut f).unwrap();
+        assert_eq!(f.pixels, vec![RED; 4]);
+
+        stack.get_mut("hud").unwrap().visible = true;
+        stack.get_mut("hud").unwrap().z = -1;
+        stack.composite(&mut f).unwrap();
+        assert_eq!(f.pixels, vec![RED; 4]);
+    }
+
+    #[test]
+    fn only_changed_layers_are_redrawn() {
+        let mut stack = LayerStack::new(4, 1);
+        stack.add_canvas("background", 0, Canvas::new(4, 1, &[RED; 4]));
+        stack.add("sprites", 1);
+        let mut f = frame();
+        stack.composite(&mut f).unwrap();
+        // the first frame with only the sprites changed keeps the background's composite
+        stack.mark_dirty("sprites");
+        stack.composite(&mut f).unwrap();
+
+        // change the background without marking it, only the sprites redraw
+        stack.layers[0].canvas.pixels = vec![0; 4];
+        stack.canvas_mut("sprites").unwrap().pixels[3] = BLUE;
+        stack.composite(&mut f).unwrap();
+        assert_eq!(f.pixels, vec![RED, RED, RED, BLUE]);
+
+        stack.mark_dirty("background");
+        stack.composite(&mut f).unwrap();
+        assert_eq!(f.pixels, vec![0, 0, 0, BLUE]);
+    }
+
+    #[test]
+    fn only_changed_areas_are_damaged() {
+        let mut stack = LayerStack::new(4, 1);
+        stack.add_canvas("background", 0, Canvas::new(4, 1, &[RED; 4]));
+        stack.add_canvas("sprite", 1, Canvas::new(1, 1, &[BLUE]));
+        let mut f = frame();
+        stack.composite(&mut f).unwrap();
+        assert_eq!(f.take_damage(), vec![Rect::new(0, 0, 4, 1)]);
+
+        // where the sprite was and where it is now
+        stack.get_mut("sprite").unwrap().x = 2;
+        assert!(stack.composite(&mut f).unwrap());
+        assert_eq!(f.pixels, vec![RED, RED, BLUE, RED]);
+        assert_eq!(f.take_damage(), vec![Rect::new(0, 0, 3, 1)]);
+
+        // hidden both before and after, nothing to draw
+        stack.get_mut("sprite").unwrap().visible = false;
+        stack.composite(&mut f).unwrap();
+        assert_eq!(f.take_damage(), vec![Rect::new(2, 0, 1, 1)]);
+        stack.mark_dirty("sprite");
+        assert!(!stack.composite(&mut f).unwrap());
+        assert!(f.take_damage().is_empty());
+        assert_eq!(f.pixels, vec![RED; 4]);
+    }
+
+    #[test]
+    fn removed_layers_stay_gone() {
+        let mut stack = LayerStack::new(4, 1);
+        stack.add_canvas("background", 0, Canvas::new(4, 1, &[RED; 4]));
+        stack.add("sprites", 1);
+        let mut f = frame();
+        stack.composite(&mut f).unwrap();
+        stack.mark_dirty("sprites");
+        stack.composite(&mut f).unwrap();
+
+        stack.remove("background");
+        stack.composite(&mut f).unwrap();
+        assert_eq!(f.pixels, vec![0; 4]);
+        stack.canvas_mut("sprites").unwrap().pixels[0] = BLUE;
+        stack.composite(&mut f).unwrap();
+        assert_eq!(f.pixels, vec![BLUE, 0, 0, 0]);
+    }
+
+    #[test]
+    fn layers_can_move_off_the_frame() {
+        let mut stack = LayerStack::new(4, 1);
+        stack.add_canvas("background", 0, Canvas::new(4, 1, &[RED; 4]));
+        stack.add_canvas("sprite", 1, Canvas::new(2, 1, &[BLUE; 2]));
+        let mut f = frame();
+        for x in [8, -8, 4, -2, 3] {
+            stack.get_mut("sprite").unwrap().x = x;
+            stack.composite(&mut f).unwrap();
+        }
+        assert_eq!(f.pixels, vec![RED, RED, RED, BLUE]);
+        stack.get_mut("sprite").unwrap().y = 1;
+        stack.composite(&mut f).unwrap();
+        assert_eq!(f.pixels, vec![RED; 4]);
+    }
+
+    #[test]
+    fn opacity_blends_layers() {
+        let mut stack = LayerStack::new(4, 1);
+        stack.add_canvas("background", 0, Canvas::new(4, 1, &[0xff00_0000; 4]));
+        stack
+            .add_canvas("fade", 1, Canvas::new(4, 1, &[0xffff_ffff; 4]))
+            .opacity = 0.5;
+        let mut f = frame();
+        stack.composite(&mut f).unwrap();
+        assert_eq!(f.pixels, vec![0xff80_8080; 4]);
+        assert!(stack.composite(&mut Canvas::new(2, 1, &[0; 2])).is_err());
+    }
+}
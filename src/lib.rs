@@ -1,15 +1,28 @@
 use std::error::Error;
 
+pub mod blend;
 pub mod blit_map;
+pub mod calibration;
 pub mod canvas;
 pub mod config;
+pub mod display;
+pub mod font;
 pub mod framebuffer;
 pub mod gesture;
+pub mod golden;
+pub mod ink;
 pub mod input;
+pub mod layer;
+mod page_flip;
+pub mod pixel_format;
 pub mod point;
 pub mod prelude;
+pub mod rect;
+mod signal;
+pub mod sprite;
 pub mod streamed_data;
 pub mod swipe;
+pub mod touch;
 pub mod util;
 
 pub fn run(
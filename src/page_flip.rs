@@ -0,0 +1,202 @@
+use crate::rect::Rect;
+
+/// Which of the two pages of a device buffer is being drawn to while the
+/// other is shown, and which parts of each are behind what has been drawn.
+#[derive(Debug)]
+pub(crate) struct PageFlip {
+    page_len: usize,
+    back_page: usize,
+    // the parts of each page older than the latest frame drawn
+    stale: [Vec<Rect>; 2],
+    // whether the back page has been drawn to since the last flip
+    written: bool,
+}
+
+impl PageFlip {
+    /// With the first page on screen, drawing to the second, neither yet
+    /// holding anything drawn.
+    pub fn new(page_len: usize, width: usize, height: usize) -> Self {
+        let all = Rect::new(0, 0, width, height);
+        PageFlip {
+            page_len,
+            back_page: 1,
+            stale: [vec![all], vec![all]],
+            written: false,
+        }
+    }
+
+    pub fn back_page(&self) -> usize {
+        self.back_page
+    }
+
+    /// The part of `frame` holding the page being drawn to.
+    pub fn back_buffer<'a>(&self, frame: &'a mut [u8]) -> &'a mut [u8] {
+        let start = self.back_page * self.page_len;
+        let end = usize::min(start + self.page_len, frame.len());
+        &mut frame[start.min(end)..end]
+    }
+
+    /// Draw a whole frame to the back page with `copy`.
+    pub fn write_frame(
+        &mut self,
+        frame: &mut [u8],
+        width: usize,
+        height: usize,
+        copy: impl FnOnce(&mut [u8]),
+    ) {
+        copy(self.back_buffer(frame));
+        self.stale[self.back_page].clear();
+        self.stale[1 - self.back_page] = vec![Rect::new(0, 0, width, height)];
+        self.written = true;
+    }
+
+    /// Draw `rect` to the back page with `copy`, first bringing whatever of
+    /// it is behind up to date from the same frame.
+    pub fn write_region(
+        &mut self,
+        frame: &mut [u8],
+        rect: Rect,
+        mut copy: impl FnMut(&mut [u8], Rect),
+    ) {
+        let back = self.back_buffer(frame);
+        for stale in std::mem::take(&mut self.stale[self.back_page]) {
+            copy(back, stale);
+        }
+        copy(back, rect);
+        // the page shown now falls behind by the same area
+        let behind = &mut self.stale[1 - self.back_page];
+        if !behind.iter().any(|r| r.contains(&rect)) {
+            behind.push(rect);
+        }
+        self.written = true;
+    }
+
+    /// Whether anything has been drawn to the back page since the last flip.
+    pub fn needs_flip(&self) -> bool {
+        self.written
+    }
+
+    /// Record that the back page is now shown, so the other is drawn to.
+    pub fn flipped(&mut self) {
+        self.back_page = 1 - self.back_page;
+        self.written = false;
+    }
+
+    /// Copy the back page over the one shown, for carrying on with only the
+    /// page on screen when flipping fails.
+    pub fn collapse(&self, frame: &mut [u8]) {
+        let back = self.back_page * self.page_len;
+        let front = (1 - self.back_page) * self.page_len;
+        if usize::max(back, front) + self.page_len <= frame.len() {
+            frame.copy_within(back..back + self.page_len, front);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel_format::PixelFormat;
+
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 2;
+    const LINE_LENGTH: usize = WIDTH * 4;
+    const PAGE_LEN: usize = LINE_LENGTH * HEIGHT;
+    const ALL: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: WIDTH,
+        height: HEIGHT,
+    };
+
+    fn page(frame: &[u8], page: usize) -> Vec<u32> {
+        let page = &frame[page * PAGE_LEN..(page + 1) * PAGE_LEN];
+        PixelFormat::XRGB8888.read_frame(page, WIDTH, HEIGHT, LINE_LENGTH)
+    }
+
+    // write `rect` of `canvas`, returning the areas copied to do it
+    fn write_region(
+        pages: &mut PageFlip,
+        frame: &mut [u8],
+        canvas: &[u32],
+        rect: Rect,
+    ) -> Vec<Rect> {
+        let mut copied = vec![];
+        pages.write_region(frame, rect, |dst, rect| {
+            copied.push(rect);
+            PixelFormat::XRGB8888.write_region(canvas, WIDTH, rect, LINE_LENGTH, dst)
+        });
+        copied
+    }
+
+    #[test]
+    fn regions_catch_up_after_flips() {
+        let mut frame = vec![0; PAGE_LEN * 2];
+        let mut pages = PageFlip::new(PAGE_LEN, WIDTH, HEIGHT);
+        assert!(!pages.needs_flip());
+        let mut canvas = vec![0xff11_1111; WIDTH * HEIGHT];
+
+        // neither page has been drawn to, so each takes the whole canvas first
+        let a = Rect::new(0, 0, 1, 1);
+        canvas[0] = 0xffaa_aaaa;
+        assert_eq!(write_region(&mut pages, &mut frame, &canvas, a), [ALL, a]);
+        assert_eq!(page(&frame, 1), canvas);
+        assert_eq!(page(&frame, 0), vec![0xff00_0000; 8]);
+        assert!(pages.needs_flip());
+        pages.flipped();
+        assert_eq!(pages.back_page(), 0);
+        assert!(!pages.needs_flip());
+
+        let b = Rect::new(3, 1, 1, 1);
+        canvas[7] = 0xffbb_bbbb;
+        assert_eq!(write_region(&mut pages, &mut frame, &canvas, b), [ALL, b]);
+        assert_eq!(page(&frame, 0), canvas);
+        pages.flipped();
+
+        // from here each page is only missing what went to the other
+        let c = Rect::new(1, 1, 1, 1);
+        canvas[5] = 0xffcc_cccc;
+        assert_eq!(write_region(&mut pages, &mut frame, &canvas, c), [b, c]);
+        assert_eq!(page(&frame, 1), canvas);
+        pages.flipped();
+        let d = Rect::new(2, 0, 1, 1);
+        canvas[2] = 0xffdd_dddd;
+        assert_eq!(write_region(&mut pages, &mut frame, &canvas, d), [c, d]);
+        assert_eq!(page(&frame, 0), canvas);
+    }
+
+    #[test]
+    fn whole_frames_leave_the_other_page_behind() {
+        let mut frame = vec![0; PAGE_LEN * 2];
+        let mut pages = PageFlip::new(PAGE_LEN, WIDTH, HEIGHT);
+        let mut canvas = vec![0xff11_1111; WIDTH * HEIGHT];
+        pages.write_frame(&mut frame, WIDTH, HEIGHT, |dst| {
+            PixelFormat::XRGB8888.write_frame(&canvas, WIDTH, HEIGHT, LINE_LENGTH, dst)
+        });
+        assert_eq!(page(&frame, 1), canvas);
+        assert!(pages.needs_flip());
+        pages.flipped();
+
+        let a = Rect::new(0, 0, 1, 1);
+        canvas[0] = 0xffaa_aaaa;
+        assert_eq!(write_region(&mut pages, &mut frame, &canvas, a), [ALL, a]);
+        assert_eq!(page(&frame, 0), canvas);
+        pages.flipped();
+        assert_eq!(write_region(&mut pages, &mut frame, &canvas, a), [a, a]);
+        assert_eq!(page(&frame, 1), canvas);
+    }
+
+    #[test]
+    fn collapse_copies_the_back_page_forward() {
+        let mut frame: Vec<u8> = (0..PAGE_LEN * 2).map(|i| i as u8).collect();
+        let pages = PageFlip::new(PAGE_LEN, WIDTH, HEIGHT);
+        pages.collapse(&mut frame);
+        assert_eq!(frame[..PAGE_LEN], frame[PAGE_LEN..]);
+        assert_eq!(frame[0], PAGE_LEN as u8);
+        // a buffer too short for two pages is left alone
+        let mut short = vec![1; PAGE_LEN];
+        pages.collapse(&mut short);
+        assert_eq!(short, vec![1; PAGE_LEN]);
+        assert!(pages.back_buffer(&mut short).is_empty());
+    }
+}
@@ -0,0 +1,437 @@
+use crate::rect::Rect;
+use framebuffer::{Bitfield, VarScreeninfo};
+
+/// Where a color channel lives inside a device pixel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Channel {
+    pub offset: u32,
+    pub length: u32,
+}
+
+impl Channel {
+    pub const fn new(offset: u32, length: u32) -> Self {
+        Channel { offset, length }
+    }
+
+    fn from_bitfield(b: &Bitfield) -> Self {
+        Channel::new(b.offset, b.length)
+    }
+
+    // scale an 8 bit channel value into this channel's bits
+    fn pack(&self, value: u8) -> u32 {
+        if self.length == 0 {
+            return 0;
+        }
+        let v = if self.length <= 8 {
+            (value as u32) >> (8 - self.length)
+        } else {
+            (value as u32) << (self.length - 8)
+        };
+        v << self.offset
+    }
+
+    // scale this channel's bits back into an 8 bit value
+    fn unpack(&self, pixel: u32) -> u8 {
+        if self.length == 0 {
+            return 0;
+        }
+        let mask = if self.length >= 32 {
+            u32::MAX
+        } else {
+            (1 << self.length) - 1
+        };
+        let v = (pixel >> self.offset) & mask;
+        if self.length >= 8 {
+            (v >> (self.length - 8)) as u8
+        } else {
+            // replicate the high bits so full intensity stays full intensity
+            ((v * 255 + mask / 2) / mask) as u8
+        }
+    }
+}
+
+/// The memory layout of a framebuffer pixel, as described by `var_screeninfo`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PixelFormat {
+    pub bits_per_pixel: u32,
+    pub grayscale: bool,
+    pub red: Channel,
+    pub green: Channel,
+    pub blue: Channel,
+    pub transp: Channel,
+}
+
+impl PixelFormat {
+    /// The layout `Canvas` pixels already use, 0xAARRGGBB.
+    pub const ARGB8888: PixelFormat = PixelFormat::rgb(32, (16, 8), (8, 8), (0, 8), (24, 8));
+    pub const XRGB8888: PixelFormat = PixelFormat::rgb(32, (16, 8), (8, 8), (0, 8), (0, 0));
+    pub const ABGR8888: PixelFormat = PixelFormat::rgb(32, (0, 8), (8, 8), (16, 8), (24, 8));
+    pub const XBGR8888: PixelFormat = PixelFormat::rgb(32, (0, 8), (8, 8), (16, 8), (0, 0));
+    pub const RGB888: PixelFormat = PixelFormat::rgb(24, (16, 8), (8, 8), (0, 8), (0, 0));
+    pub const BGR888: PixelFormat = PixelFormat::rgb(24, (0, 8), (8, 8), (16, 8), (0, 0));
+    pub const RGB565: PixelFormat = PixelFormat::rgb(16, (11, 5), (5, 6), (0, 5), (0, 0));
+    pub const BGR565: PixelFormat = PixelFormat::rgb(16, (0, 5), (5, 6), (11, 5), (0, 0));
+    pub const RGB555: PixelFormat = PixelFormat::rgb(16, (10, 5), (5, 5), (0, 5), (0, 0));
+    pub const GRAY8: PixelFormat = PixelFormat {
+        bits_per_pixel: 8,
+        grayscale: true,
+        red: Channel::new(0, 8),
+        green: Channel::new(0, 8),
+        blue: Channel::new(0, 8),
+        transp: Channel::new(0, 0),
+    };
+
+    const fn rgb(
+        bits_per_pixel: u32,
+        red: (u32, u32),
+        green: (u32, u32),
+        blue: (u32, u32),
+        transp: (u32, u32),
+    ) -> Self {
+        PixelFormat {
+            bits_per_pixel,
+            grayscale: false,
+            red: Channel::new(red.0, red.1),
+            green: Channel::new(green.0, green.1),
+            blue: Channel::new(blue.0, blue.1),
+            transp: Channel::new(transp.0, transp.1),
+        }
+    }
+
+    pub fn from_var_screeninfo(info: &VarScreeninfo) -> Self {
+        let format = PixelFormat {
+            bits_per_pixel: info.bits_per_pixel,
+            // values above 1 are fourcc codes, which we don't support
+            grayscale: info.grayscale == 1,
+            red: Channel::from_bitfield(&info.red),
+            green: Channel::from_bitfield(&info.green),
+            blue: Channel::from_bitfield(&info.blue),
+            transp: Channel::from_bitfield(&info.transp),
+        };
+        // some drivers leave the bitfields empty, guess from the depth
+        if !format.grayscale && format.red.length == 0 && format.green.length == 0 {
+            return match info.bits_per_pixel {
+                16 => PixelFormat::RGB565,
+                24 => PixelFormat::RGB888,
+                8 => PixelFormat::GRAY8,
+                _ => PixelFormat::XRGB8888,
+            };
+        }
+        format
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.bits_per_pixel.div_ceil(8) as usize
+    }
+
+    /// Convert a 0xAARRGGBB canvas color into this format's pixel value.
+    pub fn pack(&self, color: u32) -> u32 {
+        let r = (color >> 16) as u8;
+        let g = (color >> 8) as u8;
+        let b = color as u8;
+        if self.grayscale {
+            // ITU-R BT.601 luma
+            let luma = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114 + 500) / 1000;
+            return Channel::new(0, self.bits_per_pixel.min(8)).pack(luma as u8);
+        }
+        // the screen itself is always opaque
+        self.red.pack(r) | self.green.pack(g) | self.blue.pack(b) | self.transp.pack(255)
+    }
+
+    /// Convert a pixel value in this format back into an opaque 0xAARRGGBB color.
+    pub fn unpack(&self, pixel: u32) -> u32 {
+        let (r, g, b) = if self.grayscale {
+            let l = Channel::new(0, self.bits_per_pixel.min(8)).unpack(pixel);
+            (l, l, l)
+        } else {
+            (
+                self.red.unpack(pixel),
+                self.green.unpack(pixel),
+                self.blue.unpack(pixel),
+            )
+        };
+        255 << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32
+    }
+
+    /// Pack one row of canvas colors into `dst`, which must hold at least
+    /// `src.len() * bytes_per_pixel()` bytes.
+    pub fn write_row(&self, src: &[u32], dst: &mut [u8]) {
+        let bpp = self.bytes_per_pixel();
+        for (color, out) in src.iter().zip(dst.chunks_exact_mut(bpp)) {
+            let pixel = self.pack(*color);
+            match bpp {
+                1 => out[0] = pixel as u8,
+                2 => out.copy_from_slice(&(pixel as u16).to_ne_bytes()),
+                4 => out.copy_from_slice(&pixel.to_ne_bytes()),
+                _ => out.copy_from_slice(&pixel.to_le_bytes()[..bpp]),
+            }
+        }
+    }
+
+    /// Read one row of device pixels from `src` back into canvas colors.
+    pub fn read_row(&self, src: &[u8], dst: &mut [u32]) {
+        let bpp = self.bytes_per_pixel();
+        for (color, input) in dst.iter_mut().zip(src.chunks_exact(bpp)) {
+            let pixel = match bpp {
+                1 => input[0] as u32,
+                2 => u16::from_ne_bytes([input[0], input[1]]) as u32,
+                4 => u32::from_ne_bytes([input[0], input[1], input[2], input[3]]),
+                _ => {
+                    let mut bytes = [0; 4];
+                    bytes[..bpp].copy_from_slice(input);
+                    u32::from_le_bytes(bytes)
+                }
+            };
+            *color = self.unpack(pixel);
+        }
+    }
+
+    /// Pack a whole `width` x `height` canvas into `dst`, starting each row
+    /// `line_length` bytes after the previous one.
+    pub fn write_frame(
+        &self,
+        pixels: &[u32],
+        width: usize,
+        height: usize,
+        line_length: usize,
+        dst: &mut [u8],
+    ) {
+        if width == 0 {
+            return;
+        }
+        let row_bytes = width * self.bytes_per_pixel();
+        for (y, src) in pixels.chunks_exact(width).take(height).enumerate() {
+            let start = y * line_length;
+            if start + row_bytes > dst.len() {
+                break;
+            }
+            self.write_row(src, &mut dst[start..start + row_bytes]);
+        }
+    }
+
+    /// Read a `width` x `height` frame back out of a device buffer whose rows
+    /// start `line_length` bytes apart. Rows past the end of `src` stay black.
+    pub fn read_frame(
+        &self,
+        src: &[u8],
+        width: usize,
+        height: usize,
+        line_length: usize,
+    ) -> Vec<u32> {
+        if width == 0 || height == 0 {
+            return vec![];
+        }
+        let mut pixels = vec![0xff00_0000; width * height];
+        let row_bytes = width * self.bytes_per_pixel();
+        for (y, dst) in pixels.chunks_exact_mut(width).enumerate() {
+            let start = y * line_length;
+            if start + row_bytes > src.len() {
+                break;
+            }
+            self.read_row(&src[start..start + row_bytes], dst);
+        }
+        pixels
+    }
+
+    /// Convert just the pixels within `rect` of a `width` pixel wide canvas
+    /// into the matching place of a device buffer.
+    pub fn write_region(
+        &self,
+        pixels: &[u32],
+        width: usize,
+        rect: Rect,
+        line_length: usize,
+        dst: &mut [u8],
+    ) {
+        let bpp = self.bytes_per_pixel();
+        for y in rect.y..rect.bottom() {
+            let src = &pixels[y * width + rect.x..y * width + rect.right()];
+            let start = y * line_length + rect.x * bpp;
+            let end = start + rect.width * bpp;
+            if end > dst.len() {
+                break;
+            }
+            self.write_row(src, &mut dst[start..end]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::color_from_rgb;
+
+    fn convert(format: PixelFormat, colors: &[u32]) -> Vec<u8> {
+        let mut out = vec![0; colors.len() * format.bytes_per_pixel()];
+        format.write_row(colors, &mut out);
+        out
+    }
+
+    fn round_trip(format: PixelFormat, colors: &[u32]) -> Vec<u32> {
+        let bytes = convert(format, colors);
+        let mut out = vec![0; colors.len()];
+        format.read_row(&bytes, &mut out);
+        out
+    }
+
+    #[test]
+    fn xrgb8888() {
+        let c = color_from_rgb(0x12, 0x34, 0x56);
+        assert_eq!(
+            convert(PixelFormat::XRGB8888, &[c]),
+            0x0012_3456u32.to_ne_bytes()
+        );
+        assert_eq!(round_trip(PixelFormat::XRGB8888, &[c]), vec![c]);
+    }
+
+    #[test]
+    fn argb8888_is_opaque() {
+        let c = 0x0012_3456;
+        assert_eq!(
+            convert(PixelFormat::ARGB8888, &[c]),
+            0xff12_3456u32.to_ne_bytes()
+        );
+    }
+
+    #[test]
+    fn abgr8888() {
+        let c = color_from_rgb(0x12, 0x34, 0x56);
+        assert_eq!(
+            convert(PixelFormat::ABGR8888, &[c]),
+            0xff56_3412u32.to_ne_bytes()
+        );
+        assert_eq!(round_trip(PixelFormat::XBGR8888, &[c]), vec![c]);
+    }
+
+    #[test]
+    fn rgb888() {
+        let c = color_from_rgb(0x12, 0x34, 0x56);
+        assert_eq!(
+            convert(PixelFormat::RGB888, &[c, c]),
+            vec![0x56, 0x34, 0x12, 0x56, 0x34, 0x12]
+        );
+        assert_eq!(round_trip(PixelFormat::RGB888, &[c]), vec![c]);
+    }
+
+    #[test]
+    fn bgr888() {
+        let c = color_from_rgb(0x12, 0x34, 0x56);
+        assert_eq!(convert(PixelFormat::BGR888, &[c]), vec![0x12, 0x34, 0x56]);
+        assert_eq!(round_trip(PixelFormat::BGR888, &[c]), vec![c]);
+    }
+
+    #[test]
+    fn rgb565() {
+        let red = color_from_rgb(255, 0, 0);
+        let green = color_from_rgb(0, 255, 0);
+        let blue = color_from_rgb(0, 0, 255);
+        let bytes = convert(PixelFormat::RGB565, &[red, green, blue]);
+        assert_eq!(&bytes[0..2], &0xf800u16.to_ne_bytes());
+        assert_eq!(&bytes[2..4], &0x07e0u16.to_ne_bytes());
+        assert_eq!(&bytes[4..6], &0x001fu16.to_ne_bytes());
+        assert_eq!(
+            round_trip(PixelFormat::RGB565, &[red, green, blue]),
+            vec![red, green, blue]
+        );
+    }
+
+    #[test]
+    fn bgr565() {
+        let red = color_from_rgb(255, 0, 0);
+        let bytes = convert(PixelFormat::BGR565, &[red]);
+        assert_eq!(bytes, 0x001fu16.to_ne_bytes());
+    }
+
+    #[test]
+    fn rgb555() {
+        let c = color_from_rgb(0x80, 0x80, 0x80);
+        let bytes = convert(PixelFormat::RGB555, &[c]);
+        assert_eq!(bytes, (0x10 << 10 | 0x10 << 5 | 0x10u16).to_ne_bytes());
+    }
+
+    #[test]
+    fn gray8() {
+        let white = color_from_rgb(255, 255, 255);
+        let green = color_from_rgb(0, 255, 0);
+        assert_eq!(convert(PixelFormat::GRAY8, &[white, green]), vec![255, 150]);
+        assert_eq!(round_trip(PixelFormat::GRAY8, &[white]), vec![white]);
+    }
+
+    #[test]
+    fn frame_honors_stride() {
+        let c = color_from_rgb(0x12, 0x34, 0x56);
+        let pixels = vec![c; 2 * 2];
+        // 2 pixels of 3 bytes per row, padded to 8 bytes
+        let mut dst = vec![0xee; 16];
+        PixelFormat::RGB888.write_frame(&pixels, 2, 2, 8, &mut dst);
+        assert_eq!(
+            dst,
+            vec![
+                0x56, 0x34, 0x12, 0x56, 0x34, 0x12, 0xee, 0xee, //
+                0x56, 0x34, 0x12, 0x56, 0x34, 0x12, 0xee, 0xee,
+            ]
+        );
+    }
+
+    #[test]
+    fn region_honors_stride() {
+        let c = color_from_rgb(0x12, 0x34, 0x56);
+        let pixels = vec![c; 3 * 2];
+        // 3 pixels of 2 bytes per row, padded to 8 bytes
+        let mut dst = vec![0xee; 16];
+        PixelFormat::RGB565.write_region(&pixels, 3, Rect::new(1, 1, 2, 1), 8, &mut dst);
+        let packed = PixelFormat::RGB565.pack(c) as u16;
+        let [a, b] = packed.to_ne_bytes();
+        assert_eq!(
+            dst,
+            vec![
+                0xee, 0xee, 0xee, 0xee, 0xee, 0xee, 0xee, 0xee, //
+                0xee, 0xee, a, b, a, b, 0xee, 0xee,
+            ]
+        );
+    }
+
+    #[test]
+    fn read_frame_honors_stride() {
+        let c = color_from_rgb(0x12, 0x34, 0x56);
+        let pixels = vec![c, 0xff00_0000, 0xff00_0000, c];
+        // 2 pixels of 4 bytes per row, padded to 12 bytes
+        let mut dst = vec![0xee; 24];
+        PixelFormat::XRGB8888.write_frame(&pixels, 2, 2, 12, &mut dst);
+        assert_eq!(PixelFormat::XRGB8888.read_frame(&dst, 2, 2, 12), pixels);
+    }
+
+    #[test]
+    fn empty_frames() {
+        let mut dst = vec![0xee; 8];
+        PixelFormat::XRGB8888.write_frame(&[], 0, 2, 8, &mut dst);
+        assert_eq!(dst, vec![0xee; 8]);
+        assert!(PixelFormat::XRGB8888.read_frame(&dst, 0, 2, 8).is_empty());
+        assert!(PixelFormat::XRGB8888.read_frame(&dst, 2, 0, 8).is_empty());
+    }
+
+    #[test]
+    fn from_var_screeninfo_reads_bitfields() {
+        let field = |offset, length| Bitfield {
+            offset,
+            length,
+            msb_right: 0,
+        };
+        let info = VarScreeninfo {
+            bits_per_pixel: 16,
+            red: field(11, 5),
+            green: field(5, 6),
+            blue: field(0, 5),
+            ..Default::default()
+        };
+        assert_eq!(PixelFormat::from_var_screeninfo(&info), PixelFormat::RGB565);
+        let info = VarScreeninfo {
+            bits_per_pixel: 32,
+            ..Default::default()
+        };
+        assert_eq!(
+            PixelFormat::from_var_screeninfo(&info),
+            PixelFormat::XRGB8888
+        );
+    }
+}
@@ -1,9 +1,18 @@
+pub use crate::blend::BlendMode;
 pub use crate::blit_map::BlitMap;
-pub use crate::canvas::Canvas;
+pub use crate::calibration::{Calibration, Calibrator};
+pub use crate::canvas::{Canvas, FillRule, Filter, LineCap, LineJoin, Sampling, Stroke, Transform};
 pub use crate::config::Config;
 pub use crate::config::Event;
 pub use crate::config::RunResponse;
-pub use crate::gesture::Gesture;
+pub use crate::font::Font;
+pub use crate::gesture::{Gesture, MultiTouchGesture};
+pub use crate::ink::{Ink, InkPoint};
+pub use crate::input::Orientation;
+pub use crate::layer::{Layer, LayerStack};
+pub use crate::rect::Rect;
 pub use crate::run;
+pub use crate::sprite::{AnimatedSprite, Clip, LoopMode, SpriteSheet};
+pub use crate::touch::Touch;
 pub use crate::util::{color_from_rgb, load_image, random};
 pub use std::error::Error;
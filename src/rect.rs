@@ -0,0 +1,108 @@
+/// An area of pixels, such as a part of the screen that needs redrawing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The part of the area at `x`, `y`, which may be off the top or left,
+    /// that lies within `bounds_width` by `bounds_height`, if any does.
+    pub fn clipped(
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+        bounds_width: usize,
+        bounds_height: usize,
+    ) -> Option<Rect> {
+        let left = x.max(0);
+        let top = y.max(0);
+        let right = (x + width as isize).min(bounds_width as isize);
+        let bottom = (y + height as isize).min(bounds_height as isize);
+        if left >= right || top >= bottom {
+            return None;
+        }
+        Some(Rect::new(
+            left as usize,
+            top as usize,
+            (right - left) as usize,
+            (bottom - top) as usize,
+        ))
+    }
+
+    pub fn right(&self) -> usize {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> usize {
+        self.y + self.height
+    }
+
+    pub fn area(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn contains(&self, other: &Rect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// Whether the two overlap or share an edge or corner.
+    pub fn touches(&self, other: &Rect) -> bool {
+        self.x <= other.right()
+            && other.x <= self.right()
+            && self.y <= other.bottom()
+            && other.y <= self.bottom()
+    }
+
+    /// The smallest rectangle covering both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clips_to_bounds() {
+        assert_eq!(
+            Rect::clipped(-2, 3, 5, 10, 8, 8),
+            Some(Rect::new(0, 3, 3, 5))
+        );
+        assert_eq!(Rect::clipped(8, 0, 5, 5, 8, 8), None);
+        assert_eq!(Rect::clipped(0, 0, 0, 5, 8, 8), None);
+    }
+
+    #[test]
+    fn unions_touching_rects() {
+        let a = Rect::new(0, 0, 2, 2);
+        let b = Rect::new(2, 2, 1, 1);
+        assert!(a.touches(&b));
+        assert!(!a.touches(&Rect::new(3, 0, 1, 1)));
+        assert_eq!(a.union(&b), Rect::new(0, 0, 3, 3));
+        assert!(a.union(&b).contains(&b));
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+
+const HANDLED: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+
+// the last termination signal received, or 0
+static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    // only async-signal-safe work belongs here, the run loop does the rest
+    RECEIVED.store(signal, Ordering::SeqCst);
+    // if the app is stuck and never gets back to the run loop, sending the
+    // signal again kills it the usual way
+    unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+    }
+}
+
+/// Catches SIGINT, SIGTERM and SIGHUP for as long as it is alive so the run
+/// loop can put the terminal back before exiting. Only the first of each is
+/// caught, a second one ends the process.
+pub struct SignalHandlers {
+    previous: Vec<(libc::c_int, libc::sighandler_t)>,
+}
+
+impl SignalHandlers {
+    pub fn install() -> Self {
+        RECEIVED.store(0, Ordering::SeqCst);
+        let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        let previous = HANDLED
+            .iter()
+            .map(|&signal| (signal, unsafe { libc::signal(signal, handler) }))
+            .collect();
+        SignalHandlers { previous }
+    }
+
+    pub fn received(&self) -> Option<i32> {
+        match RECEIVED.load(Ordering::SeqCst) {
+            0 => None,
+            signal => Some(signal),
+        }
+    }
+}
+
+impl Drop for SignalHandlers {
+    fn drop(&mut self) {
+        for (signal, handler) in self.previous.iter() {
+            unsafe {
+                libc::signal(*signal, *handler);
+            }
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use crate::blend::BlendMode;
+use crate::canvas::{Canvas, Sampling, Transform};
+use crate::sprite::SpriteSheet;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// What a clip does after its last frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Stop on the last frame.
+    Once,
+    /// Start over from the first frame.
+    Loop,
+    /// Play backwards to the first frame, then forwards again.
+    PingPong,
+}
+
+/// A named run of sheet frames, each shown for its own number of milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub frames: Vec<usize>,
+    pub durations: Vec<usize>,
+    pub mode: LoopMode,
+}
+
+impl Clip {
+    /// Show each of `frames` for `duration` milliseconds.
+    pub fn new(frames: Vec<usize>, duration: usize, mode: LoopMode) -> Self {
+        Clip {
+            durations: vec![duration; frames.len()],
+            frames,
+            mode,
+        }
+    }
+}
+
+/// Plays clips from a sprite sheet, advanced by the time between
+/// `Event::Timer`s.
+#[derive(Debug)]
+pub struct AnimatedSprite {
+    pub sheet: Arc<SpriteSheet>,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub mode: BlendMode,
+    pub opacity: f32,
+    clips: HashMap<String, Clip>,
+    playing: Option<String>,
+    // position in the playing clip, and time spent on it
+    position: usize,
+    elapsed: usize,
+    backwards: bool,
+    finished: bool,
+}
+
+impl AnimatedSprite {
+    /// A sprite showing the sheet's first frame, knowing the clips its atlas tagged.
+    pub fn new(sheet: Arc<SpriteSheet>) -> Self {
+        let clips = sheet.clips.iter().cloned().collect();
+        AnimatedSprite {
+            sheet,
+            flip_x: false,
+            flip_y: false,
+            mode: BlendMode::Normal,
+            opacity: 1.0,
+            clips,
+            playing: None,
+            position: 0,
+            elapsed: 0,
+            backwards: false,
+            finished: false,
+        }
+    }
+
+    /// Add a clip, replacing any called `name`. Replacing the playing clip
+    /// starts it over.
+    pub fn add_clip(&mut self, name: &str, clip: Clip) -> Result<(), &'static str> {
+        check_clip(&clip)?;
+        self.clips.insert(name.to_string(), clip);
+        if self.playing.as_deref() == Some(name) {
+            self.restart();
+        }
+        Ok(())
+    }
+
+    /// Switch to the clip called `name` from its start, carrying on if it's
+    /// already playing.
+    pub fn play(&mut self, name: &str) -> Result<(), &'static str> {
+        let clip = self.clips.get(name).ok_or("no clip with that name")?;
+        check_clip(clip)?;
+        if self.playing.as_deref() != Some(name) {
+            self.playing = Some(name.to_string());
+            self.restart();
+        }
+        Ok(())
+    }
+
+    /// Go back to the first frame of the playing clip.
+    pub fn restart(&mut self) {
+        self.position = 0;
+        self.elapsed = 0;
+        self.backwards = false;
+        self.finished = false;
+    }
+
+    pub fn playing(&self) -> Option<&str> {
+        self.playing.as_deref()
+    }
+
+    /// Whether a clip that plays once has reached its last frame.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Move the animation on by `delta` milliseconds, such as the first
+    /// value of an `Event::Timer`.
+    pub fn update(&mut self, delta: usize) {
+        let clips = &self.clips;
+        let clip = match self.playing.as_ref().and_then(|name| clips.get(name)) {
+            Some(clip) => clip,
+            None => return,
+        };
+        if self.finished {
+            return;
+        }
+        self.elapsed += delta;
+        let last = clip.frames.len() - 1;
+        // a frame always lasts at least a millisecond, so this ends
+        while self.elapsed >= usize::max(clip.durations[self.position], 1) {
+            self.elapsed -= usize::max(clip.durations[self.position], 1);
+            match clip.mode {
+                LoopMode::Once if self.position == last => {
+                    self.finished = true;
+                    self.elapsed = 0;
+                    return;
+                }
+                LoopMode::Once => self.position += 1,
+                LoopMode::Loop => self.position = (self.position + 1) % clip.frames.len(),
+                LoopMode::PingPong if last == 0 => {}
+                LoopMode::PingPong => {
+                    if self.position == last {
+                        self.backwards = true;
+                    } else if self.position == 0 {
+                        self.backwards = false;
+                    }
+                    if self.backwards {
+                        self.position -= 1;
+                    } else {
+                        self.position += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The index into the sheet of the frame showing now.
+    pub fn frame(&self) -> usize {
+        self.playing
+            .as_ref()
+            .and_then(|name| self.clips.get(name))
+            .map_or(0, |clip| clip.frames[self.position])
+    }
+
+    /// Draw the frame showing now with its top left corner at `x`, `y`.
+    pub fn draw(&self, canvas: &mut Canvas, x: isize, y: isize) -> Result<(), &'static str> {
+        let frame = self
+            .sheet
+            .frames
+            .get(self.frame())
+            .ok_or("sprite frame is not in the sheet")?;
+        canvas.draw_canvas_flipped(
+            &frame.canvas,
+            x,
+            y,
+            self.flip_x,
+            self.flip_y,
+            self.mode,
+            self.opacity,
+        )
+    }
+
+    /// Draw the frame showing now through `transform`, such as a rotation
+    /// about its middle, ignoring `flip_x` and `flip_y`.
+    pub fn draw_transformed(
+        &self,
+        canvas: &mut Canvas,
+        transform: &Transform,
+        sampling: Sampling,
+    ) -> Result<(), &'static str> {
+        let frame = self
+            .sheet
+            .frames
+            .get(self.frame())
+            .ok_or("sprite frame is not in the sheet")?;
+        canvas.draw_canvas_transformed(&frame.canvas, transform, sampling, self.mode, self.opacity)
+    }
+}
+
+fn check_clip(clip: &Clip) -> Result<(), &'static str> {
+    if clip.frames.is_empty() || clip.durations.len() != clip.frames.len() {
+        return Err("clip needs a duration for each of its frames");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sprite() -> AnimatedSprite {
+        let sheet = Canvas::new(3, 1, &[0xff00_0001, 0xff00_0002, 0xff00_0003]);
+        let sheet = SpriteSheet::from_grid(&sheet, 1, 1).unwrap();
+        let mut sprite = AnimatedSprite::new(Arc::new(sheet));
+        let clips = [
+            ("once", Clip::new(vec![0, 1, 2], 10, LoopMode::Once)),
+            ("loop", Clip::new(vec![2, 0], 10, LoopMode::Loop)),
+            ("pingpong", Clip::new(vec![0, 1, 2], 10, LoopMode::PingPong)),
+        ];
+        for (name, clip) in clips {
+            sprite.add_clip(name, clip).unwrap();
+        }
+        sprite
+    }
+
+    fn frames(sprite: &mut AnimatedSprite, steps: usize) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                let frame = sprite.frame();
+                sprite.update(10);
+                frame
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loop_modes() {
+        let mut sprite = sprite();
+        sprite.play("once").unwrap();
+        assert_eq!(frames(&mut sprite, 5), vec![0, 1, 2, 2, 2]);
+        assert!(sprite.is_finished());
+
+        sprite.play("loop").unwrap();
+        assert_eq!(frames(&mut sprite, 5), vec![2, 0, 2, 0, 2]);
+
+        sprite.play("pingpong").unwrap();
+        assert_eq!(frames(&mut sprite, 7), vec![0, 1, 2, 1, 0, 1, 2]);
+        assert!(sprite.play("missing").is_err());
+    }
+
+    #[test]
+    fn durations_carry_over() {
+        let mut sprite = sprite();
+        sprite
+            .add_clip(
+                "uneven",
+                Clip {
+                    frames: vec![0, 1],
+                    durations: vec![5, 20],
+                    mode: LoopMode::Loop,
+                },
+            )
+            .unwrap();
+        sprite.play("uneven").unwrap();
+        sprite.update(4);
+        assert_eq!(sprite.frame(), 0);
+        // one long tick can pass several frames
+        sprite.update(27);
+        assert_eq!(sprite.frame(), 1);
+        // playing the same clip again doesn't restart it
+        sprite.play("uneven").unwrap();
+        sprite.update(18);
+        assert_eq!(sprite.frame(), 1);
+        sprite.update(1);
+        assert_eq!(sprite.frame(), 0);
+    }
+
+    #[test]
+    fn replacing_the_playing_clip_restarts_it() {
+        let mut sprite = sprite();
+        sprite.play("once").unwrap();
+        sprite.update(25);
+        assert_eq!(sprite.frame(), 2);
+        sprite
+            .add_clip("once", Clip::new(vec![1], 10, LoopMode::Once))
+            .unwrap();
+        assert_eq!(sprite.frame(), 1);
+        sprite.update(10);
+        assert!(sprite.is_finished());
+
+        assert!(sprite
+            .add_clip("empty", Clip::new(vec![], 10, LoopMode::Loop))
+            .is_err());
+        let uneven = Clip {
+            frames: vec![0, 1],
+            durations: vec![10],
+            mode: LoopMode::Loop,
+        };
+        assert!(sprite.add_clip("once", uneven).is_err());
+        assert_eq!(sprite.frame(), 1);
+    }
+
+    #[test]
+    fn draws_off_screen() {
+        let sheet = Canvas::new(2, 2, &[0xffff_ffff; 4]);
+        let mut sprite =
+            AnimatedSprite::new(Arc::new(SpriteSheet::from_grid(&sheet, 2, 2).unwrap()));
+        let mut canvas = Canvas::new(3, 2, &[0; 6]);
+        for flip in [false, true] {
+            sprite.flip_x = flip;
+            sprite.draw(&mut canvas, -3, 0).unwrap();
+            sprite.draw(&mut canvas, 3, 0).unwrap();
+            sprite.draw(&mut canvas, 0, 2).unwrap();
+        }
+        assert_eq!(canvas.pixels, vec![0; 6]);
+    }
+
+    #[test]
+    fn draws_flipped() {
+        let sheet = Canvas::new(2, 2, &[1, 2, 3, 4].map(|p| 0xff00_0000 | p));
+        let sheet = SpriteSheet::from_grid(&sheet, 2, 2).unwrap();
+        let mut sprite = AnimatedSprite::new(Arc::new(sheet));
+        let mut canvas = Canvas::new(3, 2, &[0; 6]);
+        sprite.flip_x = true;
+        sprite.draw(&mut canvas, 1, 0).unwrap();
+        let c = |p: u32| 0xff00_0000 | p;
+        assert_eq!(canvas.pixels, vec![0, c(2), c(1), 0, c(4), c(3)]);
+
+        sprite.flip_x = false;
+        sprite.flip_y = true;
+        sprite.draw(&mut canvas, -1, 0).unwrap();
+        assert_eq!(canvas.pixels, vec![c(4), c(2), c(1), c(2), c(4), c(3)]);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::blend::BlendMode;
+use crate::canvas::Canvas;
+use crate::util::load_image;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use std::error::Error;
+use std::path::Path;
+
+mod animation;
+pub use animation::{AnimatedSprite, Clip, LoopMode};
+
+// how long a frame shows when the sheet doesn't say
+const DEFAULT_DURATION: usize = 100;
+
+/// One image cut from a sprite sheet.
+#[derive(Debug)]
+pub struct Frame {
+    pub name: String,
+    pub canvas: Canvas,
+    // milliseconds, as given by the atlas
+    pub duration: usize,
+}
+
+/// The frames of a sprite sheet, and any animations its atlas tagged.
+#[derive(Debug, Default)]
+pub struct SpriteSheet {
+    pub frames: Vec<Frame>,
+    pub clips: Vec<(String, Clip)>,
+}
+
+impl SpriteSheet {
+    /// Cut `canvas` into frames of `frame_width` by `frame_height`, left to
+    /// right then top to bottom, named by their index. Partial frames along
+    /// the right and bottom edges are left out.
+    pub fn from_grid(
+        canvas: &Canvas,
+        frame_width: usize,
+        frame_height: usize,
+    ) -> Result<Self, &'static str> {
+        if frame_width == 0 || frame_height == 0 {
+            return Err("sprite frames must be at least one pixel");
+        }
+        let mut frames = vec![];
+        for row in 0..canvas.height / frame_height {
+            for column in 0..canvas.width / frame_width {
+                frames.push(Frame {
+                    name: frames.len().to_string(),
+                    canvas: crop(
+                        canvas,
+                        column * frame_width,
+                        row * frame_height,
+                        frame_width,
+                        frame_height,
+                    )?,
+                    duration: DEFAULT_DURATION,
+                });
+            }
+        }
+        Ok(SpriteSheet {
+            frames,
+            clips: vec![],
+        })
+    }
+
+    /// Cut `canvas` into the frames listed by a TexturePacker or Aseprite
+    /// style JSON atlas, either as a hash or an array of frames. Trimmed frames
+    /// are padded back out to their source size and Aseprite's frame tags
+    /// become clips.
+    pub fn from_atlas(canvas: &Canvas, atlas: &str) -> Result<Self, Box<dyn Error>> {
+        let atlas: Atlas = serde_json::from_str(atlas)?;
+        let entries: Vec<(String, AtlasFrame)> = match atlas.frames {
+            AtlasFrames::Hash(entries) => entries.into_iter().collect(),
+            AtlasFrames::Array(entries) => entries
+                .into_iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    let name = entry.filename.clone();
+                    (name.unwrap_or_else(|| i.to_string()), entry)
+                })
+                .collect(),
+        };
+        let mut frames = vec![];
+        for (name, entry) in entries {
+            if entry.rotated {
+                return Err("rotated atlas frames are not supported".into());
+            }
+            let AtlasRect { x, y, w, h } = entry.frame;
+            let mut frame = crop(canvas, x, y, w, h)?;
+            if entry.trimmed {
+                let offset = entry
+                    .sprite_source_size
+                    .ok_or("trimmed frame has no offset")?;
+                let size = entry
+                    .source_size
+                    .ok_or("trimmed frame has no source size")?;
+                let (sw, sh) = (size.w, size.h);
+                let mut padded = Canvas::new(sw, sh, &vec![0; sw * sh]);
+                let (ox, oy) = (offset.x as isize, offset.y as isize);
+                padded.draw_canvas(&frame, ox, oy, BlendMode::Replace, 1.0)?;
+                padded.take_damage();
+                frame = padded;
+            }
+            frames.push(Frame {
+                name,
+                canvas: frame,
+                duration: entry.duration.unwrap_or(DEFAULT_DURATION),
+            });
+        }
+
+        let mut clips = vec![];
+        for tag in atlas.meta.frame_tags {
+            let (from, to) = (tag.from, tag.to);
+            if from > to || to >= frames.len() {
+                return Err("atlas frame tag is out of range".into());
+            }
+            let mut indices: Vec<usize> = (from..=to).collect();
+            let mode = match tag.direction.as_deref() {
+                Some("pingpong") => LoopMode::PingPong,
+                Some("reverse") => {
+                    indices.reverse();
+                    LoopMode::Loop
+                }
+                _ => LoopMode::Loop,
+            };
+            let durations = indices.iter().map(|i| frames[*i].duration).collect();
+            clips.push((
+                tag.name,
+                Clip {
+                    frames: indices,
+                    durations,
+                    mode,
+                },
+            ));
+        }
+        Ok(SpriteSheet { frames, clips })
+    }
+
+    /// Load a sheet from an image and the JSON atlas describing it.
+    pub fn load_atlas<P: AsRef<Path>>(image: P, atlas: P) -> Result<Self, Box<dyn Error>> {
+        let canvas = load_image(image)?;
+        let atlas = std::fs::read_to_string(atlas)?;
+        SpriteSheet::from_atlas(&canvas, &atlas)
+    }
+
+    /// The index of the frame called `name`.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.frames.iter().position(|f| f.name == name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+// the parts of a TexturePacker or Aseprite atlas that are read
+#[derive(Deserialize)]
+struct Atlas {
+    frames: AtlasFrames,
+    #[serde(default)]
+    meta: AtlasMeta,
+}
+
+// frames keyed by name, in the order written, or listed with optional names
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AtlasFrames {
+    Hash(IndexMap<String, AtlasFrame>),
+    Array(Vec<AtlasFrame>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AtlasFrame {
+    filename: Option<String>,
+    frame: AtlasRect,
+    #[serde(default)]
+    rotated: bool,
+    #[serde(default)]
+    trimmed: bool,
+    sprite_source_size: Option<AtlasRect>,
+    source_size: Option<AtlasSize>,
+    // milliseconds
+    duration: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct AtlasRect {
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+}
+
+#[derive(Deserialize)]
+struct AtlasSize {
+    w: usize,
+    h: usize,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct AtlasMeta {
+    #[serde(default)]
+    frame_tags: Vec<AtlasTag>,
+}
+
+#[derive(Deserialize)]
+struct AtlasTag {
+    name: String,
+    from: usize,
+    to: usize,
+    direction: Option<String>,
+}
+
+fn crop(canvas: &Canvas, x: usize, y: usize, w: usize, h: usize) -> Result<Canvas, &'static str> {
+    if x + w > canvas.width || y + h > canvas.height {
+        return Err("sprite frame is outside the sheet");
+    }
+    let mut pixels = Vec::with_capacity(w * h);
+    for row in y..y + h {
+        let start = row * canvas.width + x;
+        pixels.extend_from_slice(&canvas.pixels[start..start + w]);
+    }
+    Ok(Canvas::new(w, h, &pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 4x2 sheet whose pixels are their own index
+    fn image() -> Canvas {
+        Canvas::new(4, 2, &(0..8).collect::<Vec<u32>>())
+    }
+
+    #[test]
+    fn slices_grid() {
+        let sheet = SpriteSheet::from_grid(&image(), 2, 1).unwrap();
+        assert_eq!(sheet.len(), 4);
+        assert_eq!(sheet.frames[1].canvas.pixels, vec![2, 3]);
+        assert_eq!(sheet.frames[2].canvas.pixels, vec![4, 5]);
+        assert_eq!(sheet.find("3"), Some(3));
+        // leftover columns are dropped
+        assert_eq!(SpriteSheet::from_grid(&image(), 3, 2).unwrap().len(), 1);
+        assert!(SpriteSheet::from_grid(&image(), 0, 2).is_err());
+    }
+
+    #[test]
+    fn reads_hash_atlas_with_tags() {
+        let atlas = r#"{
+            "frames": {
+                "walk 0": {"frame": {"x": 0, "y": 0, "w": 2, "h": 2}, "duration": 50},
+                "walk 1": {"frame": {"x": 2, "y": 0, "w": 1, "h": 1}, "trimmed": true,
+                    "spriteSourceSize": {"x": 1, "y": 1, "w": 1, "h": 1},
+                    "sourceSize": {"w": 2, "h": 2}, "duration": 80}
+            },
+            "meta": {"frameTags": [{"name": "walk", "from": 0, "to": 1, "direction": "pingpong"}]}
+        }"#;
+        let sheet = SpriteSheet::from_atlas(&image(), atlas).unwrap();
+        assert_eq!(sheet.find("walk 1"), Some(1));
+        assert_eq!(sheet.frames[0].canvas.pixels, vec![0, 1, 4, 5]);
+        assert_eq!(sheet.frames[0].duration, 50);
+        // padded back out to where it was trimmed from
+        assert_eq!(sheet.frames[1].canvas.pixels, vec![0, 0, 0, 2]);
+        let (name, clip) = &sheet.clips[0];
+        assert_eq!(name, "walk");
+        assert_eq!(clip.frames, vec![0, 1]);
+        assert_eq!(clip.durations, vec![50, 80]);
+        assert_eq!(clip.mode, LoopMode::PingPong);
+    }
+
+    #[test]
+    fn reads_array_atlas() {
+        let atlas = r#"{"frames": [
+            {"filename": "a", "frame": {"x": 3, "y": 1, "w": 1, "h": 1}},
+            {"frame": {"x": 0, "y": 1, "w": 1, "h": 1}}
+        ]}"#;
+        let sheet = SpriteSheet::from_atlas(&image(), atlas).unwrap();
+        assert_eq!(sheet.frames[0].name, "a");
+        assert_eq!(sheet.frames[0].canvas.pixels, vec![7]);
+        assert_eq!(sheet.frames[1].name, "1");
+        assert_eq!(sheet.frames[1].duration, DEFAULT_DURATION);
+
+        let outside = r#"{"frames": [{"frame": {"x": 3, "y": 1, "w": 2, "h": 1}}]}"#;
+        assert!(SpriteSheet::from_atlas(&image(), outside).is_err());
+
+        // an offset past the source size pads out to nothing rather than failing
+        let past = r#"{"frames": [{"frame": {"x": 0, "y": 0, "w": 1, "h": 1}, "trimmed": true,
+            "spriteSourceSize": {"x": 5, "y": 0, "w": 1, "h": 1}, "sourceSize": {"w": 2, "h": 1}}]}"#;
+        let sheet = SpriteSheet::from_atlas(&image(), past).unwrap();
+        assert_eq!(sheet.frames[0].canvas.pixels, vec![0, 0]);
+    }
+}
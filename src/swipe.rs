@@ -1,3 +1,4 @@
+use crate::calibration::Calibration;
 use crate::point::*;
 use crate::streamed_data::*;
 
@@ -59,6 +60,8 @@ pub enum SwipeFragment {
 pub struct StreamedSwipe {
     pub swipe: Option<Swipe>,
     pub streamed_point: StreamedPoint,
+    // applied to each point before it joins the swipe
+    pub calibration: Calibration,
 }
 
 impl Default for StreamedSwipe {
@@ -66,6 +69,7 @@ impl Default for StreamedSwipe {
         StreamedSwipe {
             swipe: None,
             streamed_point: StreamedPoint::Nothing,
+            calibration: Calibration::identity(),
         }
     }
 }
@@ -82,6 +86,7 @@ impl StreamedData<Swipe> for StreamedSwipe {
         match fragment {
             SwipeFragment::PointFragment(ptfrag) => match self.streamed_point.update(ptfrag) {
                 StreamedState::Complete(pt) | StreamedState::Standalone(pt) => {
+                    let pt = self.calibration.apply(&pt);
                     if let Some(updated_swipe) = self.swipe.as_mut() {
                         updated_swipe.push(pt);
                         StreamedState::Standalone(updated_swipe.clone())
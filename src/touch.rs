@@ -0,0 +1,171 @@
+use crate::calibration::Calibration;
+use crate::point::*;
+use crate::streamed_data::*;
+use crate::swipe::*;
+
+/// One finger on a multitouch screen. `id` is the contact's slot, which stays
+/// the same from the moment the finger lands until it lifts.
+#[derive(Clone, Debug)]
+pub struct Touch {
+    pub id: usize,
+    pub swipe: Swipe,
+}
+
+pub enum TouchFragment {
+    Slot(usize),
+    TrackingId(Timeval, isize),
+    X(Timeval, isize),
+    Y(Timeval, isize),
+    Sync(Timeval),
+}
+
+#[derive(Clone, Debug, Default)]
+struct SlotState {
+    active: bool,
+    lifted: bool,
+    moved: bool,
+    // the kernel only reports axes that changed, so remember the last position
+    x: Option<isize>,
+    y: Option<isize>,
+    swipe: Option<Swipe>,
+}
+
+/// Assembles evdev multitouch protocol B events into one `Swipe` per contact.
+#[derive(Clone, Debug, Default)]
+pub struct StreamedTouches {
+    slot: usize,
+    slots: Vec<SlotState>,
+    // applied to each point before it joins a swipe
+    pub calibration: Calibration,
+}
+
+impl StreamedTouches {
+    pub fn with_calibration(calibration: Calibration) -> Self {
+        StreamedTouches {
+            calibration,
+            ..Default::default()
+        }
+    }
+
+    fn current(&mut self) -> &mut SlotState {
+        if self.slots.len() <= self.slot {
+            self.slots.resize(self.slot + 1, SlotState::default());
+        }
+        &mut self.slots[self.slot]
+    }
+}
+
+impl StreamedData<Vec<Touch>> for StreamedTouches {
+    type Fragment = TouchFragment;
+
+    fn reset(&mut self) {
+        self.slot = 0;
+        self.slots.clear();
+    }
+
+    fn update(&mut self, fragment: Self::Fragment) -> StreamedState<Vec<Touch>> {
+        match fragment {
+            TouchFragment::Slot(slot) => self.slot = slot,
+            TouchFragment::TrackingId(_, id) => {
+                let s = self.current();
+                if id < 0 {
+                    s.lifted = true;
+                } else {
+                    s.active = true;
+                    s.lifted = false;
+                    s.moved = true;
+                    s.swipe = None;
+                }
+            }
+            TouchFragment::X(_, x) => {
+                let s = self.current();
+                s.x = Some(x);
+                s.moved = true;
+            }
+            TouchFragment::Y(_, y) => {
+                let s = self.current();
+                s.y = Some(y);
+                s.moved = true;
+            }
+            TouchFragment::Sync(time) => {
+                let mut touches = vec![];
+                for (id, s) in self.slots.iter_mut().enumerate() {
+                    if s.active && s.moved {
+                        if let (Some(x), Some(y)) = (s.x, s.y) {
+                            let point = self.calibration.apply(&Point { time, x, y });
+                            match s.swipe.as_mut() {
+                                Some(swipe) => swipe.push(point),
+                                None => s.swipe = Some(Swipe::new(point)),
+                            }
+                            if !s.lifted {
+                                touches.push(Touch {
+                                    id,
+                                    swipe: s.swipe.clone().unwrap(),
+                                });
+                            }
+                        }
+                    }
+                    if s.lifted {
+                        if let Some(mut swipe) = s.swipe.take() {
+                            swipe.end();
+                            touches.push(Touch { id, swipe });
+                        }
+                        s.active = false;
+                        s.lifted = false;
+                    }
+                    s.moved = false;
+                }
+                if !touches.is_empty() {
+                    return StreamedState::Standalone(touches);
+                }
+            }
+        }
+        StreamedState::Incomplete
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sync(touches: &mut StreamedTouches, ms: isize) -> Vec<Touch> {
+        match touches.update(TouchFragment::Sync(Timeval(0, ms * 1000))) {
+            StreamedState::Standalone(t) | StreamedState::Complete(t) => t,
+            StreamedState::Incomplete => vec![],
+        }
+    }
+
+    #[test]
+    fn two_fingers_get_separate_swipes() {
+        let t = Timeval(0, 0);
+        let mut touches = StreamedTouches::default();
+        touches.update(TouchFragment::Slot(0));
+        touches.update(TouchFragment::TrackingId(t, 10));
+        touches.update(TouchFragment::X(t, 1));
+        touches.update(TouchFragment::Y(t, 2));
+        touches.update(TouchFragment::Slot(1));
+        touches.update(TouchFragment::TrackingId(t, 11));
+        touches.update(TouchFragment::X(t, 50));
+        touches.update(TouchFragment::Y(t, 60));
+        let frame = sync(&mut touches, 1);
+        assert_eq!(frame.len(), 2);
+        assert_eq!((frame[0].id, frame[0].swipe.last().x), (0, 1));
+        assert_eq!((frame[1].id, frame[1].swipe.last().y), (1, 60));
+
+        // only the second finger moves, and only along x
+        touches.update(TouchFragment::X(t, 55));
+        let frame = sync(&mut touches, 2);
+        assert_eq!(frame.len(), 1);
+        assert_eq!(frame[0].id, 1);
+        assert_eq!(frame[0].swipe.points.len(), 2);
+        assert_eq!((frame[0].swipe.last().x, frame[0].swipe.last().y), (55, 60));
+
+        touches.update(TouchFragment::Slot(0));
+        touches.update(TouchFragment::TrackingId(t, -1));
+        let frame = sync(&mut touches, 3);
+        assert_eq!(frame.len(), 1);
+        assert_eq!(frame[0].id, 0);
+        assert!(frame[0].swipe.finished);
+        assert!(sync(&mut touches, 4).is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+#!/usr/bin/env python3
+# Writes test.ttf, a tiny TrueType font for the font tests: a space, a curved
+# "o", and an "A" and "V" kerned closer together. Anything else is a box.
+import os
+import struct
+
+UNITS_PER_EM = 1000
+ASCENT = 800
+DESCENT = -200
+# the kerning between "A" and "V" either way round, in font units
+AV_KERNING = -150
+
+# (char, advance, contours of (x, y, on_curve) points)
+GLYPHS = [
+    (None, 600, [[(100, 0, 1), (100, 700, 1), (500, 700, 1), (500, 0, 1)]]),
+    (" ", 300, []),
+    ("A", 600, [[(0, 0, 1), (300, 700, 1), (600, 0, 1)]]),
+    ("V", 600, [[(0, 700, 1), (600, 700, 1), (300, 0, 1)]]),
+    (
+        "o",
+        600,
+        [
+            [
+                (50, 250, 1),
+                (50, 500, 0),
+                (300, 500, 1),
+                (550, 500, 0),
+                (550, 250, 1),
+                (550, 0, 0),
+                (300, 0, 1),
+                (50, 0, 0),
+            ]
+        ],
+    ),
+]
+
+
+def glyph_data(contours):
+    if not contours:
+        return b""
+    points = [p for contour in contours for p in contour]
+    xs = [p[0] for p in points]
+    ys = [p[1] for p in points]
+    data = struct.pack(">hhhhh", len(contours), min(xs), min(ys), max(xs), max(ys))
+    end = -1
+    for contour in contours:
+        end += len(contour)
+        data += struct.pack(">H", end)
+    data += struct.pack(">H", 0)
+    data += bytes(p[2] for p in points)
+    for coords in (xs, ys):
+        last = 0
+        for v in coords:
+            data += struct.pack(">h", v - last)
+            last = v
+    return data + b"\0" * (-len(data) % 4)
+
+
+def cmap():
+    chars = sorted((ord(c), i) for i, (c, _, _) in enumerate(GLYPHS) if c)
+    segments = [(c, c, (i - c) % 65536) for c, i in chars] + [(0xFFFF, 0xFFFF, 1)]
+    count = len(segments)
+    search = 2 ** (count.bit_length() - 1)
+    body = b"".join(struct.pack(">H", s[1]) for s in segments)
+    body += struct.pack(">H", 0)
+    body += b"".join(struct.pack(">H", s[0]) for s in segments)
+    body += b"".join(struct.pack(">H", s[2]) for s in segments)
+    body += b"".join(struct.pack(">H", 0) for _ in segments)
+    header = struct.pack(
+        ">HHHHHHH",
+        4,
+        14 + len(body),
+        0,
+        count * 2,
+        search * 2,
+        search.bit_length() - 1,
+        count * 2 - search * 2,
+    )
+    return struct.pack(">HHHHI", 0, 1, 3, 1, 12) + header + body
+
+
+def font():
+    glyphs = [glyph_data(contours) for _, _, contours in GLYPHS]
+    loca = [0]
+    for g in glyphs:
+        loca.append(loca[-1] + len(g))
+    all_points = [p for _, _, cs in GLYPHS for c in cs for p in c]
+    x_max = max(p[0] for p in all_points)
+    y_max = max(p[1] for p in all_points)
+    max_points = max(sum(len(c) for c in cs) for _, _, cs in GLYPHS)
+    max_contours = max(len(cs) for _, _, cs in GLYPHS)
+
+    pairs = [(2, 3, AV_KERNING), (3, 2, AV_KERNING)]
+    kern_sub = struct.pack(">HHHH", len(pairs), 12, 1, 0)
+    kern_sub += b"".join(struct.pack(">HHh", *p) for p in pairs)
+    kern_sub = struct.pack(">HHH", 0, 6 + len(kern_sub), 0x0001) + kern_sub
+
+    tables = {
+        b"cmap": cmap(),
+        b"glyf": b"".join(glyphs),
+        b"head": struct.pack(
+            ">IIIIHHqqhhhhHHhhh",
+            0x00010000,
+            0x00010000,
+            0,
+            0x5F0F3CF5,
+            0x000B,
+            UNITS_PER_EM,
+            0,
+            0,
+            0,
+            0,
+            x_max,
+            y_max,
+            0,
+            8,
+            2,
+            0,
+            0,
+        ),
+        b"hhea": struct.pack(
+            ">IhhhHhhhhhhhhhhhH",
+            0x00010000,
+            ASCENT,
+            DESCENT,
+            0,
+            max(a for _, a, _ in GLYPHS),
+            0,
+            0,
+            x_max,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            len(GLYPHS),
+        ),
+        b"hmtx": b"".join(
+            struct.pack(">Hh", advance, min((p[0] for c in cs for p in c), default=0))
+            for _, advance, cs in GLYPHS
+        ),
+        b"kern": struct.pack(">HH", 0, 1) + kern_sub,
+        b"loca": b"".join(struct.pack(">H", offset // 2) for offset in loca),
+        b"maxp": struct.pack(
+            ">IHHHHHHHHHHHHHH",
+            0x00010000,
+            len(GLYPHS),
+            max_points,
+            max_contours,
+            0,
+            0,
+            2,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ),
+    }
+
+    count = len(tables)
+    search = 2 ** (count.bit_length() - 1)
+    out = struct.pack(
+        ">IHHHH",
+        0x00010000,
+        count,
+        search * 16,
+        search.bit_length() - 1,
+        count * 16 - search * 16,
+    )
+    offset = 12 + 16 * count
+    directory = b""
+    data = b""
+    for tag in sorted(tables):
+        table = tables[tag]
+        padded = table + b"\0" * (-len(table) % 4)
+        checksum = sum(struct.unpack(">%dI" % (len(padded) // 4), padded)) % 2**32
+        directory += struct.pack(">4sIII", tag, checksum, offset + len(data), len(table))
+        data += padded
+    return out + directory + data
+
+
+if __name__ == "__main__":
+    path = os.path.join(os.path.dirname(os.path.abspath(__file__)), "test.ttf")
+    with open(path, "wb") as f:
+        f.write(font())